@@ -0,0 +1,271 @@
+// Released under Apache License 2.0 / MIT License.
+// Copyright (c) 2024 Ladislav Bartos
+
+//! This file contains methods for working with atoms.
+
+use crate::structures::{Atom, Element};
+
+/// Mass of a hydrogen atom.
+const HYDROGEN_MASS: f64 = 1.008;
+/// Maximal deviation from `HYDROGEN_MASS` for an atom without element to be considered hydrogen.
+const HYDROGEN_MASS_TOLERANCE: f64 = 0.1;
+
+impl Atom {
+    /// Create a new atom.
+    ///
+    /// ## Parameters
+    /// - `atom_name`: name of the atom
+    /// - `atom_number`: number of the atom
+    /// - `residue_name`: name of the residue the atom is part of
+    /// - `residue_number`: number of the residue the atom is part of
+    /// - `mass`: mass of the atom
+    /// - `charge`: charge of the atom
+    ///
+    /// ## Notes
+    /// - `original_residue_number` is set to `residue_number`.
+    /// - Element, position, velocity, force, B-state mass and charge, and insertion code are `None`.
+    ///   Molecule index is 0 and chain identifier is blank.
+    /// - Optional properties can be set using the `with_*` methods.
+    ///
+    /// ## Example
+    /// ```
+    /// use minitpr::{Atom, Element};
+    ///
+    /// let atom = Atom::new("OW", 1, "SOL", 1, 15.9994, -0.834)
+    ///     .with_element(Element::O)
+    ///     .with_position([1.0, 2.0, 3.0]);
+    ///
+    /// assert_eq!(atom.element_symbol(), Some("O"));
+    /// assert!(atom.velocity.is_none());
+    /// ```
+    pub fn new(
+        atom_name: &str,
+        atom_number: i32,
+        residue_name: &str,
+        residue_number: i32,
+        mass: f64,
+        charge: f64,
+    ) -> Self {
+        Atom {
+            atom_name: atom_name.to_owned(),
+            atom_number,
+            residue_name: residue_name.to_owned(),
+            residue_number,
+            original_residue_number: residue_number,
+            mass,
+            charge,
+            mass_b: None,
+            charge_b: None,
+            element: None,
+            position: None,
+            velocity: None,
+            force: None,
+            molecule_id: 0,
+            chain_id: ' ',
+            insertion_code: None,
+        }
+    }
+
+    /// Set the element of the atom.
+    pub fn with_element(mut self, element: Element) -> Self {
+        self.element = Some(element);
+        self
+    }
+
+    /// Set the position of the atom.
+    pub fn with_position(mut self, position: [f64; 3]) -> Self {
+        self.position = Some(position);
+        self
+    }
+
+    /// Set the velocity of the atom.
+    pub fn with_velocity(mut self, velocity: [f64; 3]) -> Self {
+        self.velocity = Some(velocity);
+        self
+    }
+
+    /// Set the force acting on the atom.
+    pub fn with_force(mut self, force: [f64; 3]) -> Self {
+        self.force = Some(force);
+        self
+    }
+
+    /// Check whether the atom is a hydrogen.
+    ///
+    /// ## Notes
+    /// - If the element of the atom is known, it is used to decide.
+    /// - If the element is unknown, the atom is considered to be a hydrogen if its mass is close to 1.008.
+    pub fn is_hydrogen(&self) -> bool {
+        match self.element {
+            Some(element) => element == Element::H,
+            None => (self.mass - HYDROGEN_MASS).abs() < HYDROGEN_MASS_TOLERANCE,
+        }
+    }
+
+    /// Check whether the atom is a virtual site (or another massless dummy particle).
+    ///
+    /// ## Notes
+    /// - This is a heuristic: the atom is considered to be a virtual site if its mass is zero.
+    pub fn is_virtual_site(&self) -> bool {
+        self.mass == 0.0
+    }
+
+    /// Get the symbol of the element of the atom.
+    /// Returns `None` if the element is unknown.
+    pub fn element_symbol(&self) -> Option<&'static str> {
+        self.element.map(|element| element.symbol())
+    }
+
+    /// Get the atomic number of the element of the atom.
+    /// Returns `None` if the element is unknown.
+    pub fn atomic_number(&self) -> Option<u32> {
+        self.element.map(|element| element.atomic_number())
+    }
+
+    /// Guess the element of the atom from its name.
+    ///
+    /// ## Returns
+    /// - Element matching the leading alphabetic characters of the atom name.
+    /// - `None` if no element matches.
+    ///
+    /// ## Notes
+    /// - This is a heuristic. The leading alphabetic characters of the atom name are compared
+    ///   (case-insensitively) with element symbols, so e.g. `HD11` is guessed to be hydrogen,
+    ///   `OW` is guessed to be oxygen, and `CL-` is guessed to be chlorine.
+    /// - If both the first letter and the first two letters of the name correspond to an element
+    ///   (e.g. `CA` can be carbon or calcium), the element with the atomic weight closer
+    ///   to the mass of the atom is selected.
+    /// - Guessed elements of coarse-grained beads are typically meaningless.
+    pub fn guess_element_from_name(&self) -> Option<Element> {
+        let prefix: Vec<char> = self
+            .atom_name
+            .chars()
+            .take_while(|c| c.is_ascii_alphabetic())
+            .collect();
+
+        let one_letter = prefix
+            .first()
+            .and_then(|&c| element_from_symbol(&c.to_string()));
+        let two_letter = match prefix.get(..2) {
+            Some(chars) => element_from_symbol(&chars.iter().collect::<String>()),
+            None => None,
+        };
+
+        match (two_letter, one_letter) {
+            (Some(two), Some(one)) => {
+                let diff =
+                    |element: Element| (f64::from(element.atomic_weight()) - self.mass).abs();
+                if diff(two) < diff(one) {
+                    Some(two)
+                } else {
+                    Some(one)
+                }
+            }
+            (Some(two), None) => Some(two),
+            (None, one) => one,
+        }
+    }
+
+    /// Check whether two atoms are identical up to the specified tolerance.
+    ///
+    /// ## Parameters
+    /// - `other`: atom to compare with
+    /// - `epsilon`: maximal absolute difference between the floating-point properties of the atoms
+    ///
+    /// ## Notes
+    /// - Names, numbers, residue information, element, molecule index, chain identifier,
+    ///   and insertion code must be identical.
+    /// - Masses, charges (including B-state values), positions, velocities, and forces
+    ///   may differ by at most `epsilon`. Missing values are only equal to missing values.
+    pub fn approx_eq(&self, other: &Atom, epsilon: f64) -> bool {
+        let scalar_eq = |a: f64, b: f64| (a - b).abs() <= epsilon;
+        let optional_eq = |a: Option<f64>, b: Option<f64>| match (a, b) {
+            (Some(a), Some(b)) => scalar_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        };
+        let vector_eq = |a: Option<[f64; 3]>, b: Option<[f64; 3]>| match (a, b) {
+            (Some(a), Some(b)) => a.iter().zip(b.iter()).all(|(&x, &y)| scalar_eq(x, y)),
+            (None, None) => true,
+            _ => false,
+        };
+
+        self.atom_name == other.atom_name
+            && self.atom_number == other.atom_number
+            && self.residue_name == other.residue_name
+            && self.residue_number == other.residue_number
+            && self.original_residue_number == other.original_residue_number
+            && self.element == other.element
+            && self.molecule_id == other.molecule_id
+            && self.chain_id == other.chain_id
+            && self.insertion_code == other.insertion_code
+            && scalar_eq(self.mass, other.mass)
+            && scalar_eq(self.charge, other.charge)
+            && optional_eq(self.mass_b, other.mass_b)
+            && optional_eq(self.charge_b, other.charge_b)
+            && vector_eq(self.position, other.position)
+            && vector_eq(self.velocity, other.velocity)
+            && vector_eq(self.force, other.force)
+    }
+}
+
+impl Default for Atom {
+    /// Create an atom with empty names, zero numbers, zero mass and charge,
+    /// and no optional properties (see [`Atom::new`]).
+    fn default() -> Self {
+        Atom::new("", 0, "", 0, 0.0, 0.0)
+    }
+}
+
+/// Find element with the provided symbol. The comparison is case-insensitive.
+fn element_from_symbol(symbol: &str) -> Option<Element> {
+    Element::list()
+        .iter()
+        .find(|element| element.symbol().eq_ignore_ascii_case(symbol))
+        .copied()
+}
+
+/// Attempt to identify element from atomic number.
+/// Returns `None` if the atomic number corresponds to no element.
+pub(crate) fn from_atom_number(atomic_number: i32) -> Option<Element> {
+    if atomic_number <= 0 {
+        return None;
+    }
+
+    Element::list().get(atomic_number as usize - 1).copied()
+}
+
+/// Serialization and deserialization of elements as element symbols.
+/// This makes the serialized output independent of the `serde` support in `mendeleev`.
+#[cfg(feature = "serde")]
+pub(crate) mod element_serde {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::Element;
+
+    /// Serialize element as its symbol.
+    pub(crate) fn serialize<S: Serializer>(
+        element: &Option<Element>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        element
+            .map(|element| element.symbol())
+            .serialize(serializer)
+    }
+
+    /// Deserialize element from its symbol.
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Element>, D::Error> {
+        match Option::<String>::deserialize(deserializer)? {
+            None => Ok(None),
+            Some(symbol) => match super::element_from_symbol(&symbol) {
+                Some(element) => Ok(Some(element)),
+                None => Err(serde::de::Error::custom(format!(
+                    "unknown element symbol `{}`",
+                    symbol
+                ))),
+            },
+        }
+    }
+}
@@ -0,0 +1,120 @@
+// Released under Apache License 2.0 / MIT License.
+// Copyright (c) 2024 Ladislav Bartos
+
+//! This file contains functions for comparing topologies of tpr files.
+
+use std::collections::HashSet;
+
+use crate::structures::{Atom, Bond, TopologyDiff, TprFile};
+
+/// Maximal difference between the coordinates of positions considered identical (in nm).
+const POSITION_TOLERANCE: f64 = 1e-5;
+
+impl TprFile {
+    /// Compare the topology of this tpr file with the topology of another tpr file.
+    ///
+    /// ## Parameters
+    /// - `other`: tpr file to compare with
+    ///
+    /// ## Returns
+    /// [`TopologyDiff`](crate::TopologyDiff) structure listing the atoms and bonds
+    /// which were added, removed, or changed in `other` relative to `self`.
+    ///
+    /// ## Notes
+    /// - Atoms are matched by their index. Atoms with indices higher than the number of atoms
+    ///   in `self` are considered added, atoms with indices higher than the number of atoms
+    ///   in `other` are considered removed.
+    /// - Matched atoms are considered changed if they differ in their name, residue name,
+    ///   residue number, mass, charge, or element. Masses and charges are compared exactly.
+    /// - If positions are present in both files, they are compared with a tolerance of 1e-5 nm.
+    ///   Atoms with a position in only one of the files are considered changed.
+    /// - Bonds are compared after normalization, i.e., bonds `0-1` and `1-0` are considered identical.
+    ///   Added and removed bonds are listed in the order in which they appear in the topologies.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use minitpr::TprFile;
+    ///
+    /// let old = TprFile::parse("old.tpr").expect("Could not parse tpr file.");
+    /// let new = TprFile::parse("new.tpr").expect("Could not parse tpr file.");
+    /// assert!(old.topology_diff(&new).is_empty());
+    /// ```
+    pub fn topology_diff(&self, other: &TprFile) -> TopologyDiff {
+        let atoms = &self.topology.atoms;
+        let other_atoms = &other.topology.atoms;
+
+        let changed_atoms = atoms
+            .iter()
+            .zip(other_atoms.iter())
+            .enumerate()
+            .filter_map(|(index, (atom, other))| (!atoms_match(atom, other)).then_some(index))
+            .collect();
+
+        let bonds = normalized_bonds(&self.topology.bonds);
+        let other_bonds = normalized_bonds(&other.topology.bonds);
+
+        TopologyDiff {
+            added_atoms: (atoms.len()..other_atoms.len()).collect(),
+            removed_atoms: (other_atoms.len()..atoms.len()).collect(),
+            changed_atoms,
+            added_bonds: bonds_missing_in(&other.topology.bonds, &bonds),
+            removed_bonds: bonds_missing_in(&self.topology.bonds, &other_bonds),
+        }
+    }
+}
+
+impl TopologyDiff {
+    /// Returns `true` if no differences were found.
+    pub fn is_empty(&self) -> bool {
+        self.added_atoms.is_empty()
+            && self.removed_atoms.is_empty()
+            && self.changed_atoms.is_empty()
+            && self.added_bonds.is_empty()
+            && self.removed_bonds.is_empty()
+    }
+}
+
+/// Check whether two atoms have the same properties and positions.
+fn atoms_match(atom: &Atom, other: &Atom) -> bool {
+    let positions_match = match (atom.position, other.position) {
+        (Some(a), Some(b)) => a
+            .iter()
+            .zip(b.iter())
+            .all(|(x, y)| (x - y).abs() <= POSITION_TOLERANCE),
+        (None, None) => true,
+        _ => false,
+    };
+
+    atom.atom_name == other.atom_name
+        && atom.residue_name == other.residue_name
+        && atom.residue_number == other.residue_number
+        && atom.mass == other.mass
+        && atom.charge == other.charge
+        && atom.element == other.element
+        && positions_match
+}
+
+/// Get the set of bonds with the smaller atom index stored first.
+fn normalized_bonds(bonds: &[Bond]) -> HashSet<(usize, usize)> {
+    bonds
+        .iter()
+        .map(|bond| {
+            let bond = Bond::new(bond.atom1, bond.atom2);
+            (bond.atom1, bond.atom2)
+        })
+        .collect()
+}
+
+/// Get the (normalized) bonds from `bonds` which are not part of `set`.
+/// Each bond is only reported once.
+fn bonds_missing_in(bonds: &[Bond], set: &HashSet<(usize, usize)>) -> Vec<Bond> {
+    let mut reported = HashSet::new();
+    bonds
+        .iter()
+        .map(|bond| Bond::new(bond.atom1, bond.atom2))
+        .filter(|bond| {
+            let key = (bond.atom1, bond.atom2);
+            !set.contains(&key) && reported.insert(key)
+        })
+        .collect()
+}
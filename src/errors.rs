@@ -3,56 +3,261 @@
 
 //! This file defines errors that can be returned by the `minitpr` library.
 
-use std::path::Path;
+use std::{
+    io::IsTerminal,
+    path::Path,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        OnceLock,
+    },
+};
 
-use colored::{ColoredString, Colorize};
+use colored::{control::SHOULD_COLORIZE, ColoredString, Colorize};
+
+use crate::structures::{CoordBlock, TprHeader};
 use thiserror::Error;
 
 fn path_to_yellow(path: &Path) -> ColoredString {
-    path.to_str().unwrap().yellow()
+    paint(path.to_str().unwrap().yellow())
+}
+
+/// Override of the automatic coloring of errors: 0 = automatic, 1 = never, 2 = always.
+static COLORS_OVERRIDE: AtomicU8 = AtomicU8::new(0);
+
+/// Is stderr a terminal? Checked once, when the first error is displayed.
+static STDERR_IS_TERMINAL: OnceLock<bool> = OnceLock::new();
+
+/// Set whether errors and warnings of `minitpr` are colored, overriding the automatic detection.
+///
+/// ## Parameters
+/// - `colors`: `Some(true)` to always keep the colors, `Some(false)` to never use colors,
+///   `None` to restore the automatic detection (see [`colors_enabled`])
+///
+/// ## Notes
+/// - The colors are still subject to the global settings of the `colored` crate.
+///   To force colors when stdout is not a terminal, also call `colored::control::set_override(true)`.
+pub fn set_colors_override(colors: Option<bool>) {
+    let value = match colors {
+        None => 0,
+        Some(false) => 1,
+        Some(true) => 2,
+    };
+
+    COLORS_OVERRIDE.store(value, Ordering::Relaxed);
+}
+
+/// Decide whether errors and warnings should be colored.
+///
+/// ## Parameters
+/// - `colors_override`: override set using [`set_colors_override`]
+/// - `should_colorize`: global decision of the `colored` crate
+///   (respects `NO_COLOR`, `CLICOLOR_FORCE`, and `colored::control::set_override`)
+/// - `stderr_is_terminal`: whether stderr is a terminal
+///
+/// ## Returns
+/// The override, if set. Otherwise `true` only if `colored` allows colors and stderr is a terminal,
+/// since errors are typically written to stderr.
+pub fn colors_enabled(
+    colors_override: Option<bool>,
+    should_colorize: bool,
+    stderr_is_terminal: bool,
+) -> bool {
+    colors_override.unwrap_or(should_colorize && stderr_is_terminal)
+}
+
+/// Remove the colors from the string, unless the errors should be colored.
+fn paint(string: ColoredString) -> ColoredString {
+    let colors_override = match COLORS_OVERRIDE.load(Ordering::Relaxed) {
+        1 => Some(false),
+        2 => Some(true),
+        _ => None,
+    };
+    let stderr_is_terminal = *STDERR_IS_TERMINAL.get_or_init(|| std::io::stderr().is_terminal());
+
+    if colors_enabled(
+        colors_override,
+        SHOULD_COLORIZE.should_colorize(),
+        stderr_is_terminal,
+    ) {
+        string
+    } else {
+        string.clear()
+    }
 }
 
+/// Result of an operation that can fail with [`ParseTprError`].
+pub type TprResult<T> = Result<T, ParseTprError>;
+
 /// Errors that can occur when parsing a tpr file.
-#[derive(Error, Debug)]
+///
+/// I/O errors are stored as their kind and message, so that the errors can be cloned and compared.
+#[derive(Error, Debug, Clone, PartialEq)]
 pub enum ParseTprError {
     /// Used when the tpr file could not be opened.
-    #[error("{} file '{}' could not be opened for reading", "error:".red().bold(), path_to_yellow(.0))]
+    #[error("{} file '{}' could not be opened for reading", paint("error:".red().bold()), path_to_yellow(.0))]
     CouldNotOpen(Box<Path>),
     /// Used when expected data could not be read from a tpr file.
-    #[error("{} could not read data from a tpr file (`{}`)", "error:".red().bold(), .0.to_string().yellow())]
-    CouldNotRead(#[from] std::io::Error),
-    /// Used when the file is not a tpr file.
-    #[error("{} parsed file is not a tpr file", "error:".red().bold())]
+    #[error("{} could not read data from a tpr file (`{}`)", paint("error:".red().bold()), paint(.message.yellow()))]
+    CouldNotRead {
+        kind: std::io::ErrorKind,
+        message: String,
+    },
+    /// Used when the tpr file ends prematurely after its header has been successfully parsed.
+    /// Contains the parsed header, so that at least basic information about the file can be obtained.
+    #[error("{} tpr file is truncated (`{}`)", paint("error:".red().bold()), paint(.message.yellow()))]
+    Truncated {
+        parsed_header: Box<TprHeader>,
+        kind: std::io::ErrorKind,
+        message: String,
+    },
+    /// Used when the tpr file ends in the middle of a block of positions, velocities, or forces.
+    /// Contains the block that could not be read completely, the number of vectors read from the block,
+    /// and the expected number of vectors (i.e., the number of atoms).
+    ///
+    /// For tpr files storing the size of their body in the header (version 119 or higher),
+    /// truncation is detected before reading the body and `Truncated` is returned instead.
+    #[error("{} tpr file is truncated in the block of {} (read `{}` out of `{}` vectors)", paint("error:".red().bold()), paint(.block.to_string().yellow()), paint(.read.to_string().yellow()), paint(.expected.to_string().yellow()))]
+    TruncatedCoordinates {
+        block: CoordBlock,
+        read: usize,
+        expected: i32,
+    },
+    /// Used when a length of a string or a number of items read from the tpr file is negative
+    /// or larger than the rest of the file. The file is likely corrupted or not a tpr file.
+    #[error("{} implausible length `{}` read from the tpr file (the file is possibly corrupted)", paint("error:".red().bold()), paint(.0.to_string().yellow()))]
+    ImplausibleLength(i64),
+    /// Used when the size of the body specified in the header of the tpr file (`expected`)
+    /// does not match the size of the body in the file or the number of bytes read by the parser (`actual`).
+    /// This suggests that the file is corrupted or that the format of the file is not properly supported.
+    #[error("{} inconsistent size of the tpr file body (expected `{}` bytes, got `{}` bytes)", paint("error:".red().bold()), paint(.expected.to_string().yellow()), paint(.actual.to_string().yellow()))]
+    BodySizeMismatch { expected: i64, actual: i64 },
+    /// Used when the file is empty.
+    #[error("{} parsed file is empty", paint("error:".red().bold()))]
+    EmptyFile,
+    /// Used when the file is not a tpr file, i.e., it is too short or it does not start with the Gromacs version string.
+    #[error("{} parsed file is not a tpr file", paint("error:".red().bold()))]
     NotTpr,
     /// Used when the precision of the tpr file is not supported.
-    #[error("{} unsupported tpr file precision `{}`", "error:".red().bold(), .0.to_string().yellow())]
+    #[error("{} unsupported tpr file precision `{}`", paint("error:".red().bold()), paint(.0.to_string().yellow()))]
     UnsupportedPrecision(i32),
-    /// Used when the version of the tpr file is not supported (is older than version 103).
-    #[error("{} unsupported tpr file version `{}`", "error:".red().bold(), .0.to_string().yellow())]
+    /// Used when the version of the tpr file is not supported (is older than version 103,
+    /// or older than version 100 if `ParseOptions::allow_legacy` is set).
+    #[error("{} unsupported tpr file version `{}`", paint("error:".red().bold()), paint(.0.to_string().yellow()))]
     UnsupportedVersion(i32),
+    /// Used when the version of the tpr file is higher than `MAX_TESTED_VERSION`.
+    #[error("{} tpr file version `{}` has not been tested with minitpr (newest tested version is `{}`)", paint("error:".red().bold()), paint(.0.to_string().yellow()), paint(crate::MAX_TESTED_VERSION.to_string().yellow()))]
+    UntestedVersion(i32),
     /// Used when a symbol is requested from the SymTable that does not exist.
-    #[error("{} invalid SymTable call: `{}` is out-of-range of the SymTable", "error:".red().bold(), .0.to_string().yellow())]
+    #[error("{} invalid SymTable call: `{}` is out-of-range of the SymTable", paint("error:".red().bold()), paint(.0.to_string().yellow()))]
     IndexNotInSymTable(i32),
     /// Used when sanity check for Interaction parsing fails.
     #[error("{} discrepancy in Interaction of type `{}`: the number of instances is not divisible by the number of interacting atoms + 1",
-    "error:".red().bold(), .0.to_string().yellow())]
+    paint("error:".red().bold()), paint(.0.to_string().yellow()))]
     InteractionDiscrepancy(i32),
+    /// Used when the tpr file contains an interaction type that is not known to `minitpr`.
+    #[error("{} unknown interaction type `{}` (the tpr file was possibly written by an unsupported version of Gromacs)", paint("error:".red().bold()), paint(.0.to_string().yellow()))]
+    UnknownInteractionType(i32),
     /// Used when `interaction_type_index` for a Interaction does not exist.
-    #[error("{} interaction type index `{}` does not exist", "error:".red().bold(), .0.to_string().yellow())]
+    #[error("{} interaction type index `{}` does not exist", paint("error:".red().bold()), paint(.0.to_string().yellow()))]
     InvalidInteractionType(i32),
     /// Used when the tpr file has been parsed seemingly successfully but topology could not be constructed.
-    #[error("{} could not construct molecular topology", "error:".red().bold())]
+    #[error("{} could not construct molecular topology", paint("error:".red().bold()))]
     CouldNotConstructTopology,
     /// Used when there is an inconsistency in the number of atoms read from the TPR file.
-    #[error("{} inconsistent number of atoms in the tpr file (expected `{}` atoms, got `{}` atoms)", "error:".red().bold(), .0.to_string().yellow(), .1.to_string().yellow())]
+    #[error("{} inconsistent number of atoms in the tpr file (expected `{}` atoms, got `{}` atoms)", paint("error:".red().bold()), paint(.0.to_string().yellow()), paint(.1.to_string().yellow()))]
     InconsistentNumberOfAtoms(i32, i32),
     /// Used when an interaction classified as `bond` involves different number of atoms than 2.
-    #[error("{} invalid number of atoms (`{}`) involved in a bond", "error:".red().bold(), .0.to_string().yellow())]
+    #[error("{} invalid number of atoms (`{}`) involved in a bond", paint("error:".red().bold()), paint(.0.to_string().yellow()))]
     InvalidNumberOfBondedAtoms(usize),
     /// Used when an interaction classified as `settle` involves different number of atoms than 3.
-    #[error("{} invalid number of atoms (`{}`) involved in a settle interaction", "error".red().bold(), .0.to_string().yellow())]
+    #[error("{} invalid number of atoms (`{}`) involved in a settle interaction", paint("error".red().bold()), paint(.0.to_string().yellow()))]
     InvalidNumberOfSettleAtoms(usize),
+    /// Used when an interaction involves more atoms than supported by `AtomIndices`.
+    #[error("{} invalid number of atoms (`{}`) involved in an interaction (at most `{}` atoms are supported)", paint("error:".red().bold()), paint(.0.to_string().yellow()), paint(crate::MAX_INTERACTING_ATOMS.to_string().yellow()))]
+    TooManyInteractingAtoms(usize),
     /// Used when the size of intermolecular exclusion group is negative.
-    #[error("{} invalid intermolecular exclusion group size (expected a positive value, got `{}`)", "error:".red().bold(), .0.to_string().yellow())]
+    #[error("{} invalid intermolecular exclusion group size (expected a positive value, got `{}`)", paint("error:".red().bold()), paint(.0.to_string().yellow()))]
     InvalidIntermolecularExclusionGroupSize(i64),
+    /// Used when a bond refers to an atom that does not exist in the topology.
+    /// Contains the indices of the bonded atoms.
+    #[error("{} bond between atoms `{}` and `{}` refers to an atom which does not exist", paint("error:".red().bold()), paint(.0.to_string().yellow()), paint(.1.to_string().yellow()))]
+    InvalidBond(usize, usize),
+    /// Used when the atoms of the topology are not numbered sequentially starting from 1.
+    /// Contains the index of the atom and its number.
+    #[error("{} atom with index `{}` has number `{}` (atoms must be numbered sequentially, starting from 1)", paint("error:".red().bold()), paint(.0.to_string().yellow()), paint(.1.to_string().yellow()))]
+    NonSequentialAtomNumber(usize, i32),
+    /// Used when the residue numbers of the atoms of the topology decrease.
+    /// Contains the index of the atom and its residue number.
+    #[error("{} atom with index `{}` has residue number `{}` which is lower than the residue number of the previous atom", paint("error:".red().bold()), paint(.0.to_string().yellow()), paint(.1.to_string().yellow()))]
+    NonMonotonicResidueNumber(usize, i32),
+}
+
+impl From<std::io::Error> for ParseTprError {
+    fn from(error: std::io::Error) -> Self {
+        ParseTprError::CouldNotRead {
+            kind: error.kind(),
+            message: error.to_string(),
+        }
+    }
+}
+
+/// Anomalies encountered while parsing a tpr file which do not prevent the file from being parsed.
+///
+/// Warnings are only collected when parsing using [`TprFile::parse_with_warnings`](crate::TprFile::parse_with_warnings)
+/// or [`TprParser::parse_with_warnings`](crate::TprParser::parse_with_warnings).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseWarning {
+    /// The version of the tpr file (stored) is higher than `MAX_TESTED_VERSION`,
+    /// but the file was parsed because `ParseOptions::allow_untested_version` is set.
+    UntestedVersion(i32),
+    /// The version of the tpr file (stored) is lower than 103 (Gromacs 5.0),
+    /// but the file was parsed because `ParseOptions::allow_legacy` is set.
+    LegacyVersion(i32),
+    /// Number of interaction types renumbered to match the numbering used by newer versions of Gromacs.
+    /// Renumbering is applied to tpr files written by older versions of Gromacs.
+    RenumberedInteractionTypes(usize),
+    /// Number of atoms with no element assigned (after guessing the elements,
+    /// if `ParseOptions::fill_missing_elements` is set). Virtual sites are not counted.
+    UnknownElements(usize),
+    /// Number of duplicate bonds removed from the topology because `ParseOptions::deduplicate_bonds` is set.
+    DuplicateBondsRemoved(usize),
+}
+
+impl std::fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let prefix = paint("warning:".yellow().bold());
+        match self {
+            ParseWarning::UntestedVersion(version) => write!(
+                f,
+                "{} tpr file version `{}` has not been tested with minitpr (newest tested version is `{}`)",
+                prefix,
+                paint(version.to_string().yellow()),
+                paint(crate::MAX_TESTED_VERSION.to_string().yellow())
+            ),
+            ParseWarning::LegacyVersion(version) => write!(
+                f,
+                "{} tpr file version `{}` is a legacy version which has not been tested with minitpr",
+                prefix,
+                paint(version.to_string().yellow())
+            ),
+            ParseWarning::RenumberedInteractionTypes(n) => write!(
+                f,
+                "{} `{}` interaction type(s) renumbered to match newer versions of Gromacs",
+                prefix,
+                paint(n.to_string().yellow())
+            ),
+            ParseWarning::UnknownElements(n) => write!(
+                f,
+                "{} `{}` atom(s) with unrecognized element",
+                prefix,
+                paint(n.to_string().yellow())
+            ),
+            ParseWarning::DuplicateBondsRemoved(n) => write!(
+                f,
+                "{} `{}` duplicate bond(s) removed",
+                prefix,
+                paint(n.to_string().yellow())
+            ),
+        }
+    }
 }
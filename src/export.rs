@@ -0,0 +1,312 @@
+// Released under Apache License 2.0 / MIT License.
+// Copyright (c) 2024 Ladislav Bartos
+
+//! This file contains functions for writing the parsed tpr file in other file formats.
+
+use std::{
+    borrow::Cow,
+    io::{self, Write},
+};
+
+use crate::structures::{Element, TprFile, TprTopology};
+
+impl TprFile {
+    /// Write the system in the Gromacs GRO format.
+    ///
+    /// ## Parameters
+    /// - `writer`: destination to write the GRO file to
+    ///
+    /// ## Notes
+    /// - Requires the `export` feature.
+    /// - Residue and atom names are truncated to 5 characters.
+    ///   Residue and atom numbers wrap at 100,000.
+    /// - Velocities are written only if they are present in the tpr file.
+    /// - Atoms with no position are written with position `(0, 0, 0)`.
+    ///   If the simulation box is not present, box line with zero dimensions is written.
+    pub fn write_gro<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "{}", self.system_name)?;
+        writeln!(writer, "{:>5}", self.topology.atoms.len())?;
+
+        for atom in self.topology.atoms.iter() {
+            let position = atom.position.unwrap_or_default();
+
+            write!(
+                writer,
+                "{:>5}{:<5}{:>5}{:>5}{:>8.3}{:>8.3}{:>8.3}",
+                atom.residue_number % 100_000,
+                truncate(&atom.residue_name, 5),
+                truncate(&atom.atom_name, 5),
+                atom.atom_number % 100_000,
+                position[0],
+                position[1],
+                position[2],
+            )?;
+
+            match atom.velocity {
+                Some(velocity) if self.header.has_velocities => writeln!(
+                    writer,
+                    "{:>8.4}{:>8.4}{:>8.4}",
+                    velocity[0], velocity[1], velocity[2]
+                )?,
+                _ => writeln!(writer)?,
+            }
+        }
+
+        let simbox = self
+            .simbox
+            .as_ref()
+            .map(|simbox| simbox.simbox)
+            .unwrap_or_default();
+
+        write!(
+            writer,
+            "{:>10.5}{:>10.5}{:>10.5}",
+            simbox[0][0], simbox[1][1], simbox[2][2]
+        )?;
+
+        // off-diagonal elements are only written for triclinic boxes
+        if self.simbox.as_ref().is_some_and(|s| !s.is_orthogonal(0.0)) {
+            write!(
+                writer,
+                "{:>10.5}{:>10.5}{:>10.5}{:>10.5}{:>10.5}{:>10.5}",
+                simbox[0][1], simbox[0][2], simbox[1][0], simbox[1][2], simbox[2][0], simbox[2][1]
+            )?;
+        }
+
+        writeln!(writer)
+    }
+
+    /// Write the system in the PDB format.
+    ///
+    /// ## Parameters
+    /// - `writer`: destination to write the PDB file to
+    ///
+    /// ## Notes
+    /// - Requires the `export` feature.
+    /// - Positions and box dimensions are converted from nm to Å.
+    /// - Atoms of standard amino acid and nucleic acid residues are written as `ATOM` records,
+    ///   all other atoms are written as `HETATM` records.
+    /// - Atom numbers wrap at 100,000 and residue numbers wrap at 10,000.
+    /// - Chain identifiers are taken from `Atom::chain_id`.
+    /// - `CONECT` records are generated from the bonds of the topology.
+    /// - Atoms with no position are written with position `(0, 0, 0)`.
+    ///   `CRYST1` record is only written if the simulation box is present.
+    pub fn write_pdb<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "TITLE     {}", self.system_name)?;
+
+        if let Some(simbox) = &self.simbox {
+            let [a, b, c] = simbox.lengths();
+            let [alpha, beta, gamma] = simbox.angles();
+            writeln!(
+                writer,
+                "CRYST1{:>9.3}{:>9.3}{:>9.3}{:>7.2}{:>7.2}{:>7.2} P 1           1",
+                a * 10.0,
+                b * 10.0,
+                c * 10.0,
+                alpha,
+                beta,
+                gamma
+            )?;
+        }
+
+        for atom in self.topology.atoms.iter() {
+            let record = if STANDARD_RESIDUES.contains(&atom.residue_name.as_str()) {
+                "ATOM"
+            } else {
+                "HETATM"
+            };
+
+            // atom names shorter than 4 characters start in the 14th column
+            let atom_name = if atom.atom_name.chars().count() < 4 {
+                format!(" {}", atom.atom_name)
+            } else {
+                truncate(&atom.atom_name, 4).to_owned()
+            };
+
+            let position = atom.position.unwrap_or_default();
+
+            writeln!(
+                writer,
+                "{:<6}{:>5} {:<4} {:<4}{}{:>4}{}   {:>8.3}{:>8.3}{:>8.3}{:>6.2}{:>6.2}          {:>2}",
+                record,
+                atom.atom_number % 100_000,
+                atom_name,
+                truncate(&atom.residue_name, 4),
+                atom.chain_id,
+                atom.residue_number % 10_000,
+                atom.insertion_code.unwrap_or(' '),
+                position[0] * 10.0,
+                position[1] * 10.0,
+                position[2] * 10.0,
+                1.0,
+                0.0,
+                atom.element_symbol().unwrap_or("").to_uppercase(),
+            )?;
+        }
+
+        for (index, neighbors) in self.topology.adjacency().iter().enumerate() {
+            let number = (index + 1) % 100_000;
+            for chunk in neighbors.chunks(4) {
+                write!(writer, "CONECT{:>5}", number)?;
+                for neighbor in chunk {
+                    write!(writer, "{:>5}", (neighbor + 1) % 100_000)?;
+                }
+                writeln!(writer)?;
+            }
+        }
+
+        writeln!(writer, "END")
+    }
+
+    /// Write the properties of all atoms of the system in the CSV format.
+    ///
+    /// ## Parameters
+    /// - `writer`: destination to write the CSV file to
+    ///
+    /// ## Notes
+    /// - Requires the `export` feature.
+    /// - The first line is a header with the following columns: `atom_number`, `atom_name`,
+    ///   `residue_number`, `residue_name`, `mass`, `charge`, `element`, `x`, `y`, `z`.
+    /// - Element is written as its symbol. Unknown elements are written as empty fields.
+    /// - Masses and charges are written with 6 decimal places.
+    /// - Positions are written in nm with 4 decimal places.
+    ///   Atoms with no position have empty `x`, `y`, and `z` fields.
+    /// - Names containing commas, quotes, or line breaks are enclosed in double quotes.
+    pub fn write_atoms_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(
+            writer,
+            "atom_number,atom_name,residue_number,residue_name,mass,charge,element,x,y,z"
+        )?;
+
+        for atom in self.topology.atoms.iter() {
+            write!(
+                writer,
+                "{},{},{},{},{:.6},{:.6},{},",
+                atom.atom_number,
+                csv_field(&atom.atom_name),
+                atom.residue_number,
+                csv_field(&atom.residue_name),
+                atom.mass,
+                atom.charge,
+                atom.element_symbol().unwrap_or(""),
+            )?;
+
+            match atom.position {
+                Some(position) => writeln!(
+                    writer,
+                    "{:.4},{:.4},{:.4}",
+                    position[0], position[1], position[2]
+                )?,
+                None => writeln!(writer, ",,")?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write the bonds of the system as an edge list in the CSV format.
+    ///
+    /// ## Parameters
+    /// - `writer`: destination to write the CSV file to
+    /// - `atom_numbers`: if `true`, bonded atoms are identified by their atom numbers (starting from 1),
+    ///   otherwise by their indices (starting from 0)
+    ///
+    /// ## Notes
+    /// - Requires the `export` feature.
+    /// - The first line is a header with columns `atom1` and `atom2`.
+    ///   Each following line corresponds to one bond of `TprTopology::bonds`.
+    pub fn write_bonds_csv<W: Write>(&self, mut writer: W, atom_numbers: bool) -> io::Result<()> {
+        writeln!(writer, "atom1,atom2")?;
+
+        let offset = usize::from(atom_numbers);
+        for bond in self.topology.bonds.iter() {
+            writeln!(writer, "{},{}", bond.atom1 + offset, bond.atom2 + offset)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl TprTopology {
+    /// Write the bond graph of the system in the Graphviz DOT format.
+    ///
+    /// ## Parameters
+    /// - `writer`: destination to write the DOT file to
+    ///
+    /// ## Notes
+    /// - Requires the `export` feature.
+    /// - Each atom is written as a node labeled with the atom name and the atom number.
+    ///   Nodes are colored based on the element of the atom.
+    /// - Each bond is written as an edge between two nodes.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use minitpr::TprFile;
+    /// use std::fs::File;
+    ///
+    /// let tpr = TprFile::parse("topol.tpr").expect("Could not parse tpr file.");
+    /// let file = File::create("topol.dot").expect("Could not create file.");
+    /// tpr.topology.write_dot(file).expect("Could not write DOT file.");
+    /// ```
+    pub fn write_dot<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "graph topology {{")?;
+        writeln!(writer, "  node [style=filled];")?;
+
+        for (index, atom) in self.atoms.iter().enumerate() {
+            writeln!(
+                writer,
+                "  {} [label=\"{} {}\", fillcolor=\"{}\"];",
+                index,
+                atom.atom_name.replace('"', "\\\""),
+                atom.atom_number,
+                element_color(atom.element)
+            )?;
+        }
+
+        for bond in self.bonds.iter() {
+            writeln!(writer, "  {} -- {};", bond.atom1, bond.atom2)?;
+        }
+
+        writeln!(writer, "}}")
+    }
+}
+
+/// Get the color of an element used in DOT files.
+fn element_color(element: Option<Element>) -> &'static str {
+    match element {
+        Some(Element::H) => "#FFFFFF",
+        Some(Element::C) => "#909090",
+        Some(Element::N) => "#3050F8",
+        Some(Element::O) => "#FF0D0D",
+        Some(Element::P) => "#FF8000",
+        Some(Element::S) => "#FFFF30",
+        Some(Element::Cl) => "#1FF01F",
+        Some(_) => "#FF1493",
+        None => "#D3D3D3",
+    }
+}
+
+/// Names of residues written as `ATOM` records into PDB files.
+const STANDARD_RESIDUES: [&str; 38] = [
+    "ALA", "ARG", "ASN", "ASP", "CYS", "GLN", "GLU", "GLY", "HIS", "ILE", "LEU", "LYS", "MET",
+    "PHE", "PRO", "SER", "THR", "TRP", "TYR", "VAL", "HID", "HIE", "HIP", "HSD", "HSE", "HSP",
+    "CYX", "ASH", "GLH", "LYN", "DA", "DC", "DG", "DT", "A", "C", "G", "U",
+];
+
+/// Quote a string for use as a field of a CSV file, if necessary.
+fn csv_field(string: &str) -> Cow<'_, str> {
+    if string.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", string.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(string)
+    }
+}
+
+/// Get at most the first `max_len` characters of a string.
+#[inline(always)]
+fn truncate(string: &str, max_len: usize) -> &str {
+    match string.char_indices().nth(max_len) {
+        Some((index, _)) => &string[..index],
+        None => string,
+    }
+}
@@ -0,0 +1,145 @@
+// Released under Apache License 2.0 / MIT License.
+// Copyright (c) 2024 Ladislav Bartos
+
+//! This file contains methods for working with the atom groups used by the simulation.
+
+use crate::structures::{GroupType, SimulationGroups, TprFile};
+
+impl SimulationGroups {
+    /// Get the names of the groups used for the specified group type.
+    ///
+    /// ## Returns
+    /// Names of the groups in the order of their group numbers.
+    pub fn names_of(&self, group_type: GroupType) -> Vec<&str> {
+        self.groups
+            .get(group_type as usize)
+            .map(|groups| {
+                groups
+                    .iter()
+                    .map(|&index| {
+                        self.group_names
+                            .get(index)
+                            .map(String::as_str)
+                            .unwrap_or_default()
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Get the group number of each atom for the specified group type.
+    ///
+    /// ## Parameters
+    /// - `group_type`: type of the groups
+    /// - `n_atoms`: number of atoms in the system
+    ///
+    /// ## Returns
+    /// Vector of length `n_atoms` containing the index of the group
+    /// each atom belongs to (see [`SimulationGroups::names_of`]).
+    ///
+    /// ## Notes
+    /// - Gromacs does not store group numbers if all atoms belong to the same group.
+    ///   In such case, all atoms are assigned to group 0.
+    pub fn group_numbers_of(&self, group_type: GroupType, n_atoms: usize) -> Vec<u32> {
+        match self.group_numbers.get(group_type as usize) {
+            Some(numbers) if !numbers.is_empty() => numbers.clone(),
+            _ => vec![0; n_atoms],
+        }
+    }
+}
+
+impl TprFile {
+    /// Get the atom groups stored in the tpr file.
+    ///
+    /// ## Returns
+    /// Vector of group names and indices of the atoms in the groups.
+    /// Groups are listed in the order in which they are first used by the
+    /// group types (see [`GroupType`]). Groups with the same name and the same atoms
+    /// are only listed once.
+    ///
+    /// ## Notes
+    /// - Only the groups which are used by the simulation (e.g., for temperature coupling
+    ///   or energy output) are listed, as atoms of other index groups are not stored in the tpr file.
+    /// - Gromacs automatically adds a group `rest` containing the atoms not assigned to any
+    ///   group of a group type. This group is listed as well.
+    pub fn index_groups(&self) -> Vec<(String, Vec<usize>)> {
+        let n_atoms = self.topology.atoms.len();
+        let mut index_groups: Vec<(String, Vec<usize>)> = Vec::new();
+
+        for (type_index, groups) in self.groups.groups.iter().enumerate() {
+            let numbers = self.groups.group_numbers.get(type_index);
+
+            for (group_index, &name_index) in groups.iter().enumerate() {
+                let name = self
+                    .groups
+                    .group_names
+                    .get(name_index)
+                    .cloned()
+                    .unwrap_or_default();
+
+                let atoms: Vec<usize> = match numbers {
+                    Some(numbers) if !numbers.is_empty() => numbers
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, &number)| number as usize == group_index)
+                        .map(|(atom, _)| atom)
+                        .collect(),
+                    _ if group_index == 0 => (0..n_atoms).collect(),
+                    _ => Vec::new(),
+                };
+
+                if !index_groups.iter().any(|(n, a)| *n == name && *a == atoms) {
+                    index_groups.push((name, atoms));
+                }
+            }
+        }
+
+        index_groups
+    }
+
+    /// Get the temperature-coupling group of each atom.
+    ///
+    /// ## Returns
+    /// Vector containing the index of the temperature-coupling group for each atom.
+    /// Names of the groups can be obtained using [`TprFile::temperature_coupling_group_names`].
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use minitpr::TprFile;
+    ///
+    /// let tpr = TprFile::parse("topol.tpr").expect("Could not parse tpr file.");
+    /// let groups = tpr.temperature_coupling_groups();
+    /// let names = tpr.temperature_coupling_group_names();
+    ///
+    /// for (atom, group) in tpr.topology.atoms.iter().zip(groups) {
+    ///     println!("{} {}", atom.atom_name, names[group as usize]);
+    /// }
+    /// ```
+    pub fn temperature_coupling_groups(&self) -> Vec<u32> {
+        self.groups
+            .group_numbers_of(GroupType::TemperatureCoupling, self.topology.atoms.len())
+    }
+
+    /// Get the names of the temperature-coupling groups.
+    ///
+    /// ## Notes
+    /// - The number of groups corresponds to `n_coupling_groups` in the header of the tpr file.
+    pub fn temperature_coupling_group_names(&self) -> Vec<&str> {
+        self.groups.names_of(GroupType::TemperatureCoupling)
+    }
+
+    /// Get the energy-output group of each atom.
+    ///
+    /// ## Returns
+    /// Vector containing the index of the energy-output group for each atom.
+    /// Names of the groups can be obtained using [`TprFile::energy_group_names`].
+    pub fn energy_groups(&self) -> Vec<u32> {
+        self.groups
+            .group_numbers_of(GroupType::EnergyOutput, self.topology.atoms.len())
+    }
+
+    /// Get the names of the energy-output groups.
+    pub fn energy_group_names(&self) -> Vec<&str> {
+        self.groups.names_of(GroupType::EnergyOutput)
+    }
+}
@@ -0,0 +1,105 @@
+// Released under Apache License 2.0 / MIT License.
+// Copyright (c) 2024 Ladislav Bartos
+
+//! This file contains methods for working with the header of the tpr file.
+
+use std::fmt::Display;
+
+use crate::structures::{Precision, TprHeader};
+
+impl TprHeader {
+    /// Get the index of the initial alchemical (lambda) state.
+    ///
+    /// ## Returns
+    /// - Index into the lambda vectors of the free-energy calculation (`init-lambda-state`).
+    /// - `None` if the alchemical state is not specified (`fep_state` is negative).
+    ///
+    /// ## Notes
+    /// - Tpr files of simulations without free-energy calculations typically report state `0`.
+    /// - The lambda vectors themselves are not available, since `minitpr` does not parse the input record.
+    pub fn lambda_state(&self) -> Option<usize> {
+        usize::try_from(self.fep_state).ok()
+    }
+
+    /// Get the major version of Gromacs used to write the tpr file.
+    ///
+    /// ## Returns
+    /// - Leading numeric component of the Gromacs version, e.g. `5` for `VERSION 5.1.4`
+    ///   or `2023` for `VERSION 2023.2`.
+    /// - `None` if the version string does not contain a version number.
+    pub fn gromacs_major_version(&self) -> Option<u32> {
+        let version = self
+            .gromacs_version
+            .trim()
+            .strip_prefix("VERSION")
+            .unwrap_or(&self.gromacs_version)
+            .trim_start();
+
+        let end = version
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(version.len());
+
+        version[..end].parse().ok()
+    }
+
+    /// Check whether the tpr file has been written in double precision.
+    #[inline(always)]
+    pub fn is_double(&self) -> bool {
+        self.precision == Precision::Double
+    }
+
+    /// Get the number of bytes used to store a single real number in the tpr file (4 or 8).
+    #[inline(always)]
+    pub fn precision_bytes(&self) -> usize {
+        self.precision.n_bytes()
+    }
+}
+
+impl Precision {
+    /// Get the number of bytes used to store a single real number of this precision (4 or 8).
+    #[inline(always)]
+    pub fn n_bytes(&self) -> usize {
+        match self {
+            Precision::Single => 4,
+            Precision::Double => 8,
+        }
+    }
+}
+
+impl Display for Precision {
+    /// Write the precision as `single` or `double`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Precision::Single => write!(f, "single"),
+            Precision::Double => write!(f, "double"),
+        }
+    }
+}
+
+impl Display for TprHeader {
+    /// Write a concise human-readable summary of the tpr file header.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Gromacs version: {}", self.gromacs_version)?;
+        writeln!(
+            f,
+            "Tpr version: {} (generation {})",
+            self.tpr_version, self.tpr_generation
+        )?;
+        writeln!(f, "Precision: {}", self.precision)?;
+        writeln!(f, "Number of atoms: {}", self.n_atoms)?;
+        writeln!(f, "Simulation box: {}", yes_no(self.has_box))?;
+        writeln!(f, "Positions: {}", yes_no(self.has_positions))?;
+        writeln!(f, "Velocities: {}", yes_no(self.has_velocities))?;
+        write!(f, "Forces: {}", yes_no(self.has_forces))
+    }
+}
+
+/// Convert a boolean to `yes` or `no`.
+#[inline(always)]
+fn yes_no(value: bool) -> &'static str {
+    if value {
+        "yes"
+    } else {
+        "no"
+    }
+}
@@ -0,0 +1,107 @@
+// Released under Apache License 2.0 / MIT License.
+// Copyright (c) 2024 Ladislav Bartos
+
+//! This file contains methods for working with interactions and interaction types.
+
+use std::{fmt, ops::Deref};
+
+use crate::{
+    errors::ParseTprError,
+    structures::{AtomIndices, InteractionType},
+};
+
+impl InteractionType {
+    /// Get the name of the interaction type as used by Gromacs (e.g., `F_BONDS`).
+    pub fn as_str(&self) -> &'static str {
+        self.into()
+    }
+}
+
+impl fmt::Display for InteractionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl Deref for AtomIndices {
+    type Target = [usize];
+
+    /// Get the indices of the atoms as a slice.
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        &self.indices[..self.len as usize]
+    }
+}
+
+impl<'a> IntoIterator for &'a AtomIndices {
+    type Item = &'a usize;
+    type IntoIter = std::slice::Iter<'a, usize>;
+
+    /// Iterate over the indices of the atoms.
+    #[inline(always)]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl TryFrom<&[usize]> for AtomIndices {
+    type Error = ParseTprError;
+
+    /// Create `AtomIndices` from a slice of atom indices.
+    /// Returns `ParseTprError::TooManyInteractingAtoms` if the slice is longer than the supported number of atoms.
+    fn try_from(slice: &[usize]) -> Result<Self, Self::Error> {
+        if slice.len() > crate::MAX_INTERACTING_ATOMS {
+            return Err(ParseTprError::TooManyInteractingAtoms(slice.len()));
+        }
+
+        let mut indices = [0; crate::MAX_INTERACTING_ATOMS];
+        indices[..slice.len()].copy_from_slice(slice);
+
+        Ok(AtomIndices {
+            len: slice.len() as u8,
+            indices,
+        })
+    }
+}
+
+impl TryFrom<Vec<usize>> for AtomIndices {
+    type Error = ParseTprError;
+
+    /// Create `AtomIndices` from a vector of atom indices.
+    /// Returns `ParseTprError::TooManyInteractingAtoms` if the vector is longer than the supported number of atoms.
+    fn try_from(vector: Vec<usize>) -> Result<Self, Self::Error> {
+        AtomIndices::try_from(vector.as_slice())
+    }
+}
+
+impl From<AtomIndices> for Vec<usize> {
+    fn from(indices: AtomIndices) -> Self {
+        indices.to_vec()
+    }
+}
+
+impl PartialEq for AtomIndices {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl Eq for AtomIndices {}
+
+impl PartialEq<[usize]> for AtomIndices {
+    fn eq(&self, other: &[usize]) -> bool {
+        **self == *other
+    }
+}
+
+impl<const N: usize> PartialEq<[usize; N]> for AtomIndices {
+    fn eq(&self, other: &[usize; N]) -> bool {
+        **self == other[..]
+    }
+}
+
+impl PartialEq<Vec<usize>> for AtomIndices {
+    fn eq(&self, other: &Vec<usize>) -> bool {
+        **self == other[..]
+    }
+}
@@ -7,6 +7,9 @@
 //!
 //! ## Capabilities and Limitations
 //! - Supports parsing of tpr files from version 103 onwards (Gromacs 5.1 and later).
+//! - Tpr files with version higher than 134 ([`MAX_TESTED_VERSION`]) are rejected by default, since their format may have changed.
+//! - Tpr files version 100–102 (Gromacs 5.0) can be parsed after enabling [`ParseOptions::allow_legacy`].
+//!   This support is experimental: it was only tested on Gromacs 5.1 files converted to version 102, not on files written by Gromacs 5.0.
 //! - Extracts system topology and structure: atoms, their basic properties (including positions, velocities, and forces), and bonds between atoms (including intermolecular bonds).
 //! - Does **not** support parsing of force-field and simulation parameters, nor does it offer capabilities to write tpr files.
 //!
@@ -40,6 +43,18 @@
 //! }
 //! ```
 //!
+//! ### Parsing options
+//! Use `TprParser` to parse a tpr file with non-default options:
+//! ```rust,no_run
+//! use minitpr::TprParser;
+//!
+//! let tpr = TprParser::new()
+//!     .coordinates(false)
+//!     .deduplicate_bonds(true)
+//!     .parse("topol.tpr")
+//!     .expect("Could not parse tpr file.");
+//! ```
+//!
 //! ### Data Structures
 //! The [`TprFile`](`crate::TprFile`) structure encapsulates the following information:
 //!
@@ -47,14 +62,22 @@
 //! - Molecular System Name: The name of the simulated system.
 //! - Simulation Box Dimensions: Available within the [`SimBox`](`crate::SimBox`) structure if present.
 //! - System Topology: Topology of the molecular system containing atoms and bonds (see [`TprTopology`](`crate::TprTopology`) structure).
+//! - Molecule Blocks: Molecule type names and the number of molecules in each molecule block (see [`MolBlockView`](`crate::MolBlockView`) structure).
+//! - Molecule Types: Atoms, residues, and interactions of each molecule type (see [`MoleculeTypeView`](`crate::MoleculeTypeView`) structure).
+//! - Simulation Groups: Names of the atom groups and the assignment of atoms into the groups for the individual group types (see [`SimulationGroups`](`crate::SimulationGroups`) structure).
 //!
 //! Each atom (see [`Atom`](`crate::Atom`)) represented in the system topology includes:
 //! - Atom name.
 //! - Sequential atom number, starting from 1.
 //! - Residue name.
 //! - Sequential residue number, starting from 1.
+//! - Original residue number (as stored in the tpr file).
+//! - Index of the molecule, starting from 0.
+//! - Chain identifier (blank for single-residue molecules).
+//! - Residue insertion code (if present).
 //! - Mass.
 //! - Charge.
+//! - B-state mass and charge (`None` if identical to the A-state values).
 //! - Element (`None` if unidentifiable).
 //! - Position (`None` if not present).
 //! - Velocity (`None` if not present).
@@ -67,6 +90,14 @@
 //! cargo add minitpr --features serde
 //! ```
 //!
+//! The `TprHeader` structure can also be (de)serialized on its own, e.g., to inspect the metadata of a tpr file without serializing all of its atoms.
+//!
+//! ### Export
+//! Enable writing the parsed system in other file formats (GRO, PDB, Graphviz DOT, CSV) by adding the feature flag during installation:
+//! ```shell
+//! cargo add minitpr --features export
+//! ```
+//!
 //! ## License
 //! `minitpr` is open-sourced under either the [Apache License 2.0](https://www.apache.org/licenses/LICENSE-2.0) or the [MIT License](https://opensource.org/license/MIT) at your option.
 //!
@@ -77,20 +108,49 @@
 //! If the library is unable to parse your tpr file, but you believe it should be able to, please open a [GitHub issue](https://github.com/Ladme/minitpr/issues) and **upload your tpr file**.
 //!
 
+#![allow(clippy::needless_doctest_main)]
+
 use errors::ParseTprError;
-use std::path::Path;
+use std::{collections::HashMap, path::Path};
 
+mod atom;
+mod diff;
 pub mod errors;
+#[cfg(feature = "export")]
+mod export;
+mod groups;
+mod header;
+mod interactions;
+mod moltypes;
 mod parse;
+mod parser;
+mod simbox;
 pub mod structures;
+mod topology;
 
 pub use structures::*;
 
 /// Current version of the `minitpr` library.
 pub const MINITPR_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// Highest version of the tpr file format that has been tested with `minitpr`.
+/// Parsing newer tpr files fails with [`ParseTprError::UntestedVersion`](`crate::errors::ParseTprError::UntestedVersion`)
+/// unless [`ParseOptions::allow_untested_version`](`crate::ParseOptions::allow_untested_version`) is set.
+pub const MAX_TESTED_VERSION: i32 = 134;
+
+/// Residue names commonly used for water molecules.
+/// Used by [`TprTopology::water_atom_indices`](`crate::TprTopology::water_atom_indices`).
+pub const WATER_RESIDUE_NAMES: &[&str] = &["SOL", "WAT", "HOH", "TIP3", "W", "TIP4", "SPC"];
+
+/// Lowest version of the tpr file format that is fully supported by `minitpr` (Gromacs 5.1).
+pub(crate) const MIN_SUPPORTED_VERSION: i32 = 103;
+/// Lowest version of the tpr file format that can be parsed with `ParseOptions::allow_legacy` (Gromacs 5.0).
+pub(crate) const MIN_LEGACY_VERSION: i32 = 100;
+
 /// Number of spatial dimensions.
 pub(crate) const DIM: usize = 3;
+/// Maximal number of atoms involved in a single interaction.
+pub(crate) const MAX_INTERACTING_ATOMS: usize = 5;
 /// Number of fields in the `F_RBDIHS` and `F_FOURDIHS` function types
 pub(crate) const NR_RBDIHS: usize = 6;
 /// Number of fields in the `F_CBTDIHS` function type
@@ -98,6 +158,33 @@ pub(crate) const NR_CBTDIHS: usize = 6;
 /// Number of group types (TemperatureCoupling, EnergyOutput, Acceleration, etc.).
 pub(crate) const NR_GROUP_TYPES: usize = 10;
 
+/// Parse multiple Gromacs tpr files in parallel using the default options.
+///
+/// ## Parameters
+/// - `paths`: paths to the tpr files to read
+///
+/// ## Returns
+/// Vector containing the result of parsing for each of the files, in the order of `paths`.
+///
+/// ## Notes
+/// - Errors are reported for each file separately. A file that fails to parse
+///   does not stop the parsing of the other files.
+/// - To parse the files with non-default options, use [`TprParser::parse_many`](`crate::TprParser::parse_many`).
+///
+/// ## Example
+/// ```no_run
+/// let results = minitpr::parse_many(&["first.tpr", "second.tpr"]);
+/// for result in results {
+///     match result {
+///         Ok(tpr) => println!("{}", tpr.system_name),
+///         Err(error) => eprintln!("{}", error),
+///     }
+/// }
+/// ```
+pub fn parse_many<P: AsRef<Path> + Sync>(paths: &[P]) -> Vec<Result<TprFile, ParseTprError>> {
+    TprParser::new().parse_many(paths)
+}
+
 impl TprFile {
     /// Parse a Gromacs tpr file.
     ///
@@ -110,12 +197,276 @@ impl TprFile {
     ///
     /// ## Notes
     /// - Only tpr files version 103 or higher are supported (Gromacs 5.1 onwards).
+    ///   Tpr files version 100–102 (Gromacs 5.0) are rejected unless
+    ///   [`ParseOptions::allow_legacy`](`crate::ParseOptions::allow_legacy`) is set.
+    /// - Tpr files with version higher than [`MAX_TESTED_VERSION`] are rejected
+    ///   (see [`ParseOptions::allow_untested_version`](`crate::ParseOptions::allow_untested_version`)).
     /// - The function only parses the following information: tpr file header,
     ///   name of the system, simulation box, system topology (atoms and bonds),
     ///   positions, velocities, and forces (if present).
     /// - Force-field properties and simulation parameters are NOT parsed.
     /// - If the tpr file does not contain topology information, this function will return an error.
+    /// - To parse the tpr file with non-default options, use [`TprParser`](`crate::TprParser`).
     pub fn parse(filename: impl AsRef<Path>) -> Result<Self, ParseTprError> {
-        parse::parse_tpr(filename)
+        TprParser::new().parse(filename)
+    }
+
+    /// Parse a Gromacs tpr file stored in memory.
+    ///
+    /// ## Parameters
+    /// - `bytes`: content of the tpr file
+    ///
+    /// ## Returns
+    /// - `TprFile` structure, if successful.
+    /// - Otherwise [`ParseTprError`](`crate::errors::ParseTprError`).
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use minitpr::TprFile;
+    ///
+    /// let bytes = std::fs::read("topol.tpr").expect("Could not read file.");
+    /// let tpr = TprFile::parse_bytes(&bytes).expect("Could not parse tpr file.");
+    /// ```
+    ///
+    /// ## Notes
+    /// - Useful if the tpr file is not stored on disk, e.g., when it has been downloaded
+    ///   or when running in an environment without access to the file system.
+    /// - The bytes are parsed in the same way as a file (see [`TprFile::parse`]).
+    pub fn parse_bytes(bytes: &[u8]) -> Result<Self, ParseTprError> {
+        TprParser::new().parse_bytes(bytes)
+    }
+
+    /// Parse a Gromacs tpr file read from the standard input.
+    ///
+    /// ## Returns
+    /// - `TprFile` structure, if successful.
+    /// - Otherwise [`ParseTprError`](`crate::errors::ParseTprError`).
+    ///
+    /// ## Example
+    /// Parsing the output of `cat topol.tpr | my_tool`:
+    /// ```no_run
+    /// use minitpr::TprFile;
+    ///
+    /// let tpr = TprFile::parse_stdin().expect("Could not parse tpr file.");
+    /// ```
+    ///
+    /// ## Notes
+    /// - The standard input can not be seeked, so the whole standard input is read into memory
+    ///   before parsing (see [`TprFile::parse_bytes`]).
+    /// - Returns `ParseTprError::CouldNotRead` if the standard input could not be read.
+    pub fn parse_stdin() -> Result<Self, ParseTprError> {
+        TprParser::new().parse_stdin()
+    }
+
+    /// Parse a Gromacs tpr file using the provided options.
+    ///
+    /// ## Parameters
+    /// - `filename`: path to the tpr file to read
+    /// - `options`: options controlling the parsing (see [`ParseOptions`](`crate::ParseOptions`))
+    ///
+    /// ## Returns
+    /// - [`TprFile`](`crate::TprFile`) structure, if successful.
+    /// - Otherwise [`ParseTprError`](`crate::errors::ParseTprError`).
+    ///
+    /// ## Notes
+    /// - See [`TprFile::parse`] for more information.
+    pub fn parse_with_options(
+        filename: impl AsRef<Path>,
+        options: &ParseOptions,
+    ) -> Result<Self, ParseTprError> {
+        parse::parse_tpr(filename, options, &mut |_| (), &mut Vec::new())
+    }
+
+    /// Parse a Gromacs tpr file, collecting warnings about the anomalies encountered during the parsing.
+    ///
+    /// ## Parameters
+    /// - `filename`: path to the tpr file to read
+    ///
+    /// ## Returns
+    /// - `TprFile` structure and a vector of [`ParseWarning`](`crate::errors::ParseWarning`), if successful.
+    /// - Otherwise [`ParseTprError`](`crate::errors::ParseTprError`).
+    ///
+    /// ## Notes
+    /// - See [`TprFile::parse`] for more information.
+    /// - Warnings do not affect the parsed data. They only report e.g. atoms with unrecognized elements.
+    pub fn parse_with_warnings(
+        filename: impl AsRef<Path>,
+    ) -> Result<(Self, Vec<errors::ParseWarning>), ParseTprError> {
+        TprParser::new().parse_with_warnings(filename)
+    }
+
+    /// Get a concise human-readable summary of the tpr file.
+    ///
+    /// ## Returns
+    /// String containing the name of the system, information from the tpr file header
+    /// (see the `Display` implementation of [`TprHeader`](`crate::TprHeader`)),
+    /// and the number of residues, molecules, and bonds in the system.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use minitpr::TprFile;
+    ///
+    /// let tpr = TprFile::parse("topol.tpr").expect("Could not parse tpr file.");
+    /// eprintln!("{}", tpr.summary());
+    /// ```
+    pub fn summary(&self) -> String {
+        let n_molecules: usize = self
+            .molecule_blocks
+            .iter()
+            .map(|block| block.n_molecules)
+            .sum();
+
+        format!(
+            "System name: {}\n{}\nNumber of residues: {}\nNumber of molecules: {}\nNumber of bonds: {}",
+            self.system_name(),
+            self.header,
+            self.topology.residues().len(),
+            n_molecules,
+            self.topology.bonds.len()
+        )
+    }
+
+    /// Get the molecular composition of the system.
+    ///
+    /// ## Returns
+    /// Vector of molecule type names and the total number of molecules of each type.
+    /// The molecule types are listed in the order in which they first appear in the molecule blocks.
+    /// Molecules of the same type from different molecule blocks are counted together.
+    pub fn composition(&self) -> Vec<(String, usize)> {
+        let mut composition: Vec<(usize, String, usize)> = Vec::new();
+
+        for block in self.molecule_blocks.iter() {
+            match composition
+                .iter_mut()
+                .find(|(moltype, _, _)| *moltype == block.molecule_type)
+            {
+                Some((_, _, count)) => *count += block.n_molecules,
+                None => {
+                    composition.push((block.molecule_type, block.name.clone(), block.n_molecules))
+                }
+            }
+        }
+
+        composition
+            .into_iter()
+            .map(|(_, name, count)| (name, count))
+            .collect()
+    }
+
+    /// Get the name of the system suitable for display.
+    ///
+    /// ## Returns
+    /// - Name of the system, if it is not empty.
+    /// - `Unnamed system` if the name of the system is empty or only contains whitespace.
+    ///
+    /// ## Notes
+    /// - Trailing whitespace and control characters are removed from the name of the system during parsing.
+    pub fn system_name(&self) -> &str {
+        if self.system_name.trim().is_empty() {
+            "Unnamed system"
+        } else {
+            &self.system_name
+        }
+    }
+
+    /// Iterate over the atoms of the system.
+    ///
+    /// ## Notes
+    /// - Equivalent to `self.topology.atoms.iter()`.
+    pub fn atoms(&self) -> impl Iterator<Item = &Atom> {
+        self.topology.atoms.iter()
+    }
+
+    /// Iterate over the bonds of the system.
+    ///
+    /// ## Notes
+    /// - Equivalent to `self.topology.bonds.iter()`.
+    pub fn bonds(&self) -> impl Iterator<Item = &Bond> {
+        self.topology.bonds.iter()
+    }
+
+    /// Get the molecule types of the system.
+    ///
+    /// ## Notes
+    /// - Molecule types are listed in the order in which they appear in the tpr file.
+    /// - Each molecule type contains its atoms, residues, and interactions
+    ///   (see [`MoleculeTypeView`](`crate::MoleculeTypeView`)).
+    pub fn molecule_types(&self) -> &[MoleculeTypeView] {
+        &self.molecule_types
+    }
+
+    /// Get the molecule blocks of the system.
+    ///
+    /// ## Notes
+    /// - Molecule blocks are listed in the order in which they appear in the tpr file.
+    /// - Each molecule block refers to one of the [`TprFile::molecule_types`].
+    pub fn molecule_blocks(&self) -> &[MolBlockView] {
+        &self.molecule_blocks
+    }
+
+    /// Get the number of molecule types in the system.
+    pub fn n_molecule_types(&self) -> usize {
+        self.molecule_types.len()
+    }
+
+    /// Get the number of molecule blocks in the system.
+    ///
+    /// ## Notes
+    /// - Molecules of the same type may be split into several molecule blocks,
+    ///   so the number of molecule blocks can be higher than the number of molecule types.
+    pub fn n_molecule_blocks(&self) -> usize {
+        self.molecule_blocks.len()
+    }
+
+    /// Remove positions, velocities, and forces from the system.
+    ///
+    /// ## Notes
+    /// - Coordinates of all atoms are removed (see [`TprTopology::clear_coordinates`](`crate::TprTopology::clear_coordinates`))
+    ///   and the contiguous blocks of coordinates in [`TprFile::coordinates`] (if present) are dropped.
+    /// - The header of the tpr file is not modified.
+    pub fn clear_coordinates(&mut self) {
+        self.topology.clear_coordinates();
+        self.coordinates = None;
+    }
+
+    /// Get the molecular weights of all molecule types of the system.
+    ///
+    /// ## Returns
+    /// Vector of molecule type names and their molecular weights
+    /// (see [`MoleculeTypeView::molecular_weight`](`crate::MoleculeTypeView::molecular_weight`))
+    /// in the order in which the molecule types appear in the tpr file.
+    pub fn molecule_type_weights(&self) -> Vec<(String, f64)> {
+        self.molecule_types
+            .iter()
+            .map(|moltype| (moltype.name.clone(), moltype.molecular_weight()))
+            .collect()
+    }
+
+    /// Count the interactions of each type in the system.
+    ///
+    /// ## Returns
+    /// Map of interaction type names as used by Gromacs (e.g., `F_BONDS`, `F_SETTLE`)
+    /// to the number of interactions of that type.
+    /// Interactions of all molecules of all molecule blocks as well as the intermolecular
+    /// interactions are counted. Interaction types which are not present are not included.
+    pub fn interaction_histogram(&self) -> HashMap<String, usize> {
+        let mut histogram = HashMap::new();
+        let mut add = |interactions: &[Interaction], multiplicity: usize| {
+            for interaction in interactions {
+                *histogram
+                    .entry(interaction.interaction_type.to_string())
+                    .or_insert(0) += multiplicity;
+            }
+        };
+
+        for block in self.molecule_blocks.iter() {
+            if let Some(moltype) = self.molecule_types.get(block.molecule_type) {
+                add(&moltype.interactions, block.n_molecules);
+            }
+        }
+
+        add(&self.intermolecular_interactions, 1);
+
+        histogram
     }
 }
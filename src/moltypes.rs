@@ -0,0 +1,16 @@
+// Released under Apache License 2.0 / MIT License.
+// Copyright (c) 2024 Ladislav Bartos
+
+//! This file contains methods for working with molecule types.
+
+use crate::structures::MoleculeTypeView;
+
+impl MoleculeTypeView {
+    /// Calculate the molecular weight of the molecule type.
+    ///
+    /// ## Returns
+    /// Sum of the (A-state) masses of all atoms of the molecule type.
+    pub fn molecular_weight(&self) -> f64 {
+        self.atoms.iter().map(|atom| atom.mass).sum()
+    }
+}
@@ -3,21 +3,11 @@
 
 //! This file contains functions for parsing positions, velocities and forces.
 
-use crate::{errors::ParseTprError, Precision, TprHeader};
+use std::io::ErrorKind;
 
-use super::xdr::XdrFile;
+use crate::{errors::ParseTprError, CoordBlock, Coordinates, Precision, TprHeader};
 
-/// Structure holding the parsed positions, velocities, and forces of particles.
-/// If the vector is empty, the corresponding properties are not present in the tpr file.
-#[derive(Debug, Clone)]
-pub(super) struct Coordinates {
-    /// Positions of particles in the system.
-    pub(super) positions: Vec<[f64; 3]>,
-    /// Velocities of particles in the system.
-    pub(super) velocities: Vec<[f64; 3]>,
-    /// Forces acting upon the particles of the system.
-    pub(super) forces: Vec<[f64; 3]>,
-}
+use super::xdr::XdrFile;
 
 impl Coordinates {
     /// Get positions, velocities, and forces of particles from a tpr file.
@@ -26,19 +16,34 @@ impl Coordinates {
         tpr_header: &TprHeader,
     ) -> Result<Self, ParseTprError> {
         let positions = if tpr_header.has_positions {
-            Self::read_block(xdrfile, tpr_header.precision, tpr_header.n_atoms)?
+            Self::read_block(
+                xdrfile,
+                tpr_header.precision,
+                tpr_header.n_atoms,
+                CoordBlock::Positions,
+            )?
         } else {
             Vec::default()
         };
 
         let velocities = if tpr_header.has_velocities {
-            Self::read_block(xdrfile, tpr_header.precision, tpr_header.n_atoms)?
+            Self::read_block(
+                xdrfile,
+                tpr_header.precision,
+                tpr_header.n_atoms,
+                CoordBlock::Velocities,
+            )?
         } else {
             Vec::default()
         };
 
         let forces = if tpr_header.has_forces {
-            Self::read_block(xdrfile, tpr_header.precision, tpr_header.n_atoms)?
+            Self::read_block(
+                xdrfile,
+                tpr_header.precision,
+                tpr_header.n_atoms,
+                CoordBlock::Forces,
+            )?
         } else {
             Vec::default()
         };
@@ -51,14 +56,42 @@ impl Coordinates {
     }
 
     /// Read a block of coordinates.
+    /// Returns `ParseTprError::TruncatedCoordinates` if the file ends before the block is read completely.
     fn read_block(
         xdrfile: &mut XdrFile,
         precision: Precision,
         n_items: i32,
+        block: CoordBlock,
     ) -> Result<Vec<[f64; 3]>, ParseTprError> {
-        (0..n_items)
-            .map(|_| xdrfile.read_vector3(precision))
-            .collect::<Result<Vec<[f64; 3]>, std::io::Error>>()
-            .map_err(ParseTprError::CouldNotRead)
+        // n_items comes from the header and has not been checked yet,
+        // so do not reserve more memory than the rest of the file can fill
+        let max_items = xdrfile.remaining()? / (3 * precision.n_bytes() as u64);
+        let mut vectors = Vec::with_capacity((n_items.max(0) as u64).min(max_items) as usize);
+        for _ in 0..n_items {
+            match xdrfile.read_vector3(precision) {
+                Ok(vector) => vectors.push(vector),
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof => {
+                    return Err(ParseTprError::TruncatedCoordinates {
+                        block,
+                        read: vectors.len(),
+                        expected: n_items,
+                    })
+                }
+                Err(e) => return Err(ParseTprError::from(e)),
+            }
+        }
+
+        Ok(vectors)
+    }
+}
+
+impl std::fmt::Display for CoordBlock {
+    /// Write the name of the block of coordinates in lowercase.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CoordBlock::Positions => write!(f, "positions"),
+            CoordBlock::Velocities => write!(f, "velocities"),
+            CoordBlock::Forces => write!(f, "forces"),
+        }
     }
 }
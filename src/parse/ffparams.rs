@@ -4,11 +4,12 @@
 //! This file contains functions for obtaining force-field parameters from TPR file.
 
 use num;
-use num_derive::FromPrimitive;
-use std::collections::HashMap;
-use strum::{EnumCount, EnumIter};
+use strum::{EnumCount, IntoEnumIterator};
 
-use crate::{errors::ParseTprError, structures::Precision};
+use crate::{
+    errors::ParseTprError,
+    structures::{InteractionType, Precision},
+};
 
 use super::xdr::XdrFile;
 
@@ -18,6 +19,11 @@ use super::xdr::XdrFile;
 #[derive(Debug, Clone)]
 pub(super) struct FFParams {
     pub interaction_types: Vec<InteractionType>,
+    /// Interaction types stored for each list of interactions in the tpr file, in the order in which they are stored.
+    /// Depends only on the version of the tpr file, so it is constructed once and reused for all molecule types.
+    pub interaction_plan: Vec<InteractionType>,
+    /// Number of interaction types renumbered because the tpr file has been written by an older version of Gromacs.
+    pub n_renumbered: usize,
 }
 
 impl FFParams {
@@ -30,10 +36,10 @@ impl FFParams {
         // ignore the number of atom types
         xdrfile.jump(4)?;
         // get the number of function (interaction) types
-        let n_interaction_types = xdrfile.read_i32()?;
+        let n_interaction_types = xdrfile.read_count(4)?;
 
         // get the interaction types
-        let mut interaction_types = Vec::with_capacity(n_interaction_types as usize);
+        let mut interaction_types = Vec::with_capacity(n_interaction_types);
         for _ in 0..n_interaction_types {
             interaction_types.push(xdrfile.read_i32()?);
         }
@@ -42,25 +48,30 @@ impl FFParams {
         xdrfile.jump(8)?;
         xdrfile.skip_real(precision)?;
 
-        let mut interaction_types_enum = Vec::with_capacity(n_interaction_types as usize);
-
-        // renumber (update) the interaction types
-        let updater = FTUpdater::default();
+        let mut interaction_types_enum = Vec::with_capacity(n_interaction_types);
+        let mut n_renumbered = 0;
 
         // loop over all interaction types
         for interaction in interaction_types.iter_mut() {
-            // loop over all file versions
-            for (version, number) in &updater.update {
+            let original = *interaction;
+
+            // renumber (update) the interaction type
+            for (version, number) in FT_UPDATES {
                 // if the version of the tpr file is lower
                 // and the function type is higher than the updated function type, renumber it
                 if tpr_version < *version && *interaction >= *number {
-                    *interaction += 1;
+                    *interaction = interaction.saturating_add(1);
                 }
             }
 
-            let interaction_type_enum = num::FromPrimitive::from_i32(*interaction).expect(
-                "FATAL MINITPR ERROR | FFParams::parse | Cannot convert interaction type to enum.",
-            );
+            if *interaction != original {
+                n_renumbered += 1;
+            }
+
+            let interaction_type_enum = match num::FromPrimitive::from_i32(*interaction) {
+                Some(x) => x,
+                None => return Err(ParseTprError::UnknownInteractionType(*interaction)),
+            };
 
             interaction_types_enum.push(interaction_type_enum);
 
@@ -70,9 +81,23 @@ impl FFParams {
 
         Ok(FFParams {
             interaction_types: interaction_types_enum,
+            interaction_plan: Self::interaction_plan(tpr_version),
+            n_renumbered,
         })
     }
 
+    /// Get the interaction types stored in a list of interactions in a tpr file of the specified version.
+    /// Interaction types introduced in newer versions of the tpr file format are skipped.
+    fn interaction_plan(tpr_version: i32) -> Vec<InteractionType> {
+        InteractionType::iter()
+            .filter(|&functype| {
+                !FT_UPDATES
+                    .iter()
+                    .any(|&(version, number)| tpr_version < version && functype as i32 == number)
+            })
+            .collect()
+    }
+
     /// Read parameters for the target interaction type from the xdr file.
     /// This function does not return anything, if successful.
     /// The parameters are read and then promptly ignored as we do not need them.
@@ -219,10 +244,10 @@ impl FFParams {
             }
             InteractionType::F_GB12_NOLONGERUSED
             | InteractionType::F_GB13_NOLONGERUSED
-            | InteractionType::F_GB14_NOLONGERUSED => {
-                if tpr_version < 113 {
-                    xdrfile.skip_multiple_reals(precision, 5)?;
-                }
+            | InteractionType::F_GB14_NOLONGERUSED
+                if tpr_version < 113 =>
+            {
+                xdrfile.skip_multiple_reals(precision, 5)?;
             }
             InteractionType::F_CMAP => {
                 xdrfile.jump(8)?;
@@ -235,190 +260,114 @@ impl FFParams {
     }
 }
 
-/// Takes care of updating the interaction type numbers.
-pub(super) struct FTUpdater {
-    /// format is `file version`: `interaction type number`
-    pub update: HashMap<i32, i32>,
-}
-
-impl FTUpdater {
-    /// Create a `TprFTUpdater` structure.
-    pub(super) fn default() -> Self {
-        FTUpdater {
-            update: HashMap::from([(121, 65), (118, 67), (117, 76)]),
-        }
-    }
-}
-
-/// Enum describing all supported interaction types.
-#[derive(Debug, Clone, Copy, FromPrimitive, EnumIter, EnumCount)]
-#[allow(non_camel_case_types, dead_code)]
-pub(crate) enum InteractionType {
-    F_BONDS = 0,
-    F_G96BONDS,
-    F_MORSE,
-    F_CUBICBONDS,
-    F_CONNBONDS,
-    F_HARMONIC,
-    F_FENEBONDS,
-    F_TABBONDS,
-    F_TABBONDSNC,
-    F_RESTRBONDS,
-    F_ANGLES,
-    F_G96ANGLES,
-    F_RESTRANGLES,
-    F_LINEAR_ANGLES,
-    F_CROSS_BOND_BONDS,
-    F_CROSS_BOND_ANGLES,
-    F_UREY_BRADLEY,
-    F_QUARTIC_ANGLES,
-    F_TABANGLES,
-    F_PDIHS,
-    F_RBDIHS,
-    F_RESTRDIHS,
-    F_CBTDIHS,
-    F_FOURDIHS,
-    F_IDIHS,
-    F_PIDIHS,
-    F_TABDIHS,
-    F_CMAP,
-    F_GB12_NOLONGERUSED,
-    F_GB13_NOLONGERUSED,
-    F_GB14_NOLONGERUSED,
-    F_GBPOL_NOLONGERUSED,
-    F_NPSOLVATION_NOLONGERUSED,
-    F_LJ14,
-    F_COUL14,
-    F_LJC14_Q,
-    F_LJC_PAIRS_NB,
-    F_LJ,
-    F_BHAM,
-    F_LJ_LR_NOLONGERUSED,
-    F_BHAM_LR_NOLONGERUSED,
-    F_DISPCORR,
-    F_COUL_SR,
-    F_COUL_LR_NOLONGERUSED,
-    F_RF_EXCL,
-    F_COUL_RECIP,
-    F_LJ_RECIP,
-    F_DPD,
-    F_POLARIZATION,
-    F_WATER_POL,
-    F_THOLE_POL,
-    F_ANHARM_POL,
-    F_POSRES,
-    F_FBPOSRES,
-    F_DISRES,
-    F_DISRESVIOL,
-    F_ORIRES,
-    F_ORIRESDEV,
-    F_ANGRES,
-    F_ANGRESZ,
-    F_DIHRES,
-    F_DIHRESVIOL,
-    F_CONSTR,
-    F_CONSTRNC,
-    F_SETTLE,
-    F_VSITE1,
-    F_VSITE2,
-    F_VSITE2FD,
-    F_VSITE3,
-    F_VSITE3FD,
-    F_VSITE3FAD,
-    F_VSITE3OUT,
-    F_VSITE4FD,
-    F_VSITE4FDN,
-    F_VSITEN,
-    F_COM_PULL,
-    F_DENSITYFITTING,
-    F_EQM,
-    F_EPOT,
-    F_EKIN,
-    F_ETOT,
-    F_ECONSERVED,
-    F_TEMP,
-    F_VTEMP_NOLONGERUSED,
-    F_PDISPCORR,
-    F_PRES,
-    F_DVDL_CONSTR,
-    F_DVDL,
-    F_DKDL,
-    F_DVDL_COUL,
-    F_DVDL_VDW,
-    F_DVDL_BONDED,
-    F_DVDL_RESTRAINT,
-    F_DVDL_TEMPERATURE,
-}
+/// Interaction types introduced in specific versions of the tpr file format.
+/// Used for updating the interaction type numbers read from older tpr files.
+/// Format is `(file version, interaction type number)`, in the order used by Gromacs.
+pub(super) const FT_UPDATES: &[(i32, i32)] = &[(117, 76), (121, 65), (118, 67)];
 
 impl InteractionType {
     /// Get the number of interacting atoms for this InteractionType.
+    #[inline(always)]
     pub(super) fn n_interacting_atoms(&self) -> i32 {
-        match self {
-            InteractionType::F_POSRES | InteractionType::F_FBPOSRES => 1,
-
-            InteractionType::F_BONDS
-            | InteractionType::F_G96BONDS
-            | InteractionType::F_MORSE
-            | InteractionType::F_CUBICBONDS
-            | InteractionType::F_CONNBONDS
-            | InteractionType::F_HARMONIC
-            | InteractionType::F_FENEBONDS
-            | InteractionType::F_TABBONDS
-            | InteractionType::F_TABBONDSNC
-            | InteractionType::F_RESTRBONDS
-            | InteractionType::F_GB12_NOLONGERUSED
-            | InteractionType::F_GB13_NOLONGERUSED
-            | InteractionType::F_GB14_NOLONGERUSED
-            | InteractionType::F_LJ14
-            | InteractionType::F_LJC14_Q
-            | InteractionType::F_LJC_PAIRS_NB
-            | InteractionType::F_LJ
-            | InteractionType::F_BHAM
-            | InteractionType::F_POLARIZATION
-            | InteractionType::F_ANHARM_POL
-            | InteractionType::F_DISRES
-            | InteractionType::F_ORIRES
-            | InteractionType::F_ANGRESZ
-            | InteractionType::F_CONSTR
-            | InteractionType::F_CONSTRNC
-            | InteractionType::F_VSITE1
-            | InteractionType::F_VSITEN => 2,
-
-            InteractionType::F_ANGLES
-            | InteractionType::F_G96ANGLES
-            | InteractionType::F_RESTRANGLES
-            | InteractionType::F_LINEAR_ANGLES
-            | InteractionType::F_CROSS_BOND_BONDS
-            | InteractionType::F_CROSS_BOND_ANGLES
-            | InteractionType::F_UREY_BRADLEY
-            | InteractionType::F_QUARTIC_ANGLES
-            | InteractionType::F_TABANGLES
-            | InteractionType::F_SETTLE
-            | InteractionType::F_VSITE2
-            | InteractionType::F_VSITE2FD => 3,
-
-            InteractionType::F_PDIHS
-            | InteractionType::F_RBDIHS
-            | InteractionType::F_RESTRDIHS
-            | InteractionType::F_CBTDIHS
-            | InteractionType::F_FOURDIHS
-            | InteractionType::F_IDIHS
-            | InteractionType::F_PIDIHS
-            | InteractionType::F_TABDIHS
-            | InteractionType::F_THOLE_POL
-            | InteractionType::F_ANGRES
-            | InteractionType::F_DIHRES
-            | InteractionType::F_VSITE3
-            | InteractionType::F_VSITE3FD
-            | InteractionType::F_VSITE3FAD
-            | InteractionType::F_VSITE3OUT => 4,
-
-            InteractionType::F_CMAP
-            | InteractionType::F_WATER_POL
-            | InteractionType::F_VSITE4FD
-            | InteractionType::F_VSITE4FDN => 5,
-
-            _ => 0,
-        }
+        N_INTERACTING_ATOMS[*self as usize]
     }
 }
+
+/// Number of atoms interacting via each interaction type, indexed by the discriminant of `InteractionType`.
+/// Interaction types which are not stored as interactions between atoms have zero atoms.
+const N_INTERACTING_ATOMS: [i32; InteractionType::COUNT] = [
+    2, // F_BONDS
+    2, // F_G96BONDS
+    2, // F_MORSE
+    2, // F_CUBICBONDS
+    2, // F_CONNBONDS
+    2, // F_HARMONIC
+    2, // F_FENEBONDS
+    2, // F_TABBONDS
+    2, // F_TABBONDSNC
+    2, // F_RESTRBONDS
+    3, // F_ANGLES
+    3, // F_G96ANGLES
+    3, // F_RESTRANGLES
+    3, // F_LINEAR_ANGLES
+    3, // F_CROSS_BOND_BONDS
+    3, // F_CROSS_BOND_ANGLES
+    3, // F_UREY_BRADLEY
+    3, // F_QUARTIC_ANGLES
+    3, // F_TABANGLES
+    4, // F_PDIHS
+    4, // F_RBDIHS
+    4, // F_RESTRDIHS
+    4, // F_CBTDIHS
+    4, // F_FOURDIHS
+    4, // F_IDIHS
+    4, // F_PIDIHS
+    4, // F_TABDIHS
+    5, // F_CMAP
+    2, // F_GB12_NOLONGERUSED
+    2, // F_GB13_NOLONGERUSED
+    2, // F_GB14_NOLONGERUSED
+    0, // F_GBPOL_NOLONGERUSED
+    0, // F_NPSOLVATION_NOLONGERUSED
+    2, // F_LJ14
+    0, // F_COUL14
+    2, // F_LJC14_Q
+    2, // F_LJC_PAIRS_NB
+    2, // F_LJ
+    2, // F_BHAM
+    0, // F_LJ_LR_NOLONGERUSED
+    0, // F_BHAM_LR_NOLONGERUSED
+    0, // F_DISPCORR
+    0, // F_COUL_SR
+    0, // F_COUL_LR_NOLONGERUSED
+    0, // F_RF_EXCL
+    0, // F_COUL_RECIP
+    0, // F_LJ_RECIP
+    0, // F_DPD
+    2, // F_POLARIZATION
+    5, // F_WATER_POL
+    4, // F_THOLE_POL
+    2, // F_ANHARM_POL
+    1, // F_POSRES
+    1, // F_FBPOSRES
+    2, // F_DISRES
+    0, // F_DISRESVIOL
+    2, // F_ORIRES
+    0, // F_ORIRESDEV
+    4, // F_ANGRES
+    2, // F_ANGRESZ
+    4, // F_DIHRES
+    0, // F_DIHRESVIOL
+    2, // F_CONSTR
+    2, // F_CONSTRNC
+    3, // F_SETTLE
+    2, // F_VSITE1
+    3, // F_VSITE2
+    3, // F_VSITE2FD
+    4, // F_VSITE3
+    4, // F_VSITE3FD
+    4, // F_VSITE3FAD
+    4, // F_VSITE3OUT
+    5, // F_VSITE4FD
+    5, // F_VSITE4FDN
+    2, // F_VSITEN
+    0, // F_COM_PULL
+    0, // F_DENSITYFITTING
+    0, // F_EQM
+    0, // F_EPOT
+    0, // F_EKIN
+    0, // F_ETOT
+    0, // F_ECONSERVED
+    0, // F_TEMP
+    0, // F_VTEMP_NOLONGERUSED
+    0, // F_PDISPCORR
+    0, // F_PRES
+    0, // F_DVDL_CONSTR
+    0, // F_DVDL
+    0, // F_DKDL
+    0, // F_DVDL_COUL
+    0, // F_DVDL_VDW
+    0, // F_DVDL_BONDED
+    0, // F_DVDL_RESTRAINT
+    0, // F_DVDL_TEMPERATURE
+];
@@ -0,0 +1,51 @@
+// Released under Apache License 2.0 / MIT License.
+// Copyright (c) 2024 Ladislav Bartos
+
+//! This file contains functions for parsing the atom groups used by the simulation.
+
+use crate::{errors::ParseTprError, structures::SimulationGroups, NR_GROUP_TYPES};
+
+use super::{symtab::SymTable, xdr::XdrFile};
+
+impl SimulationGroups {
+    /// Get `SimulationGroups` from `XdrFile`.
+    pub(super) fn parse(
+        xdrfile: &mut XdrFile,
+        tpr_version: i32,
+        symbol_table: &SymTable,
+    ) -> Result<Self, ParseTprError> {
+        // read groups of each group type
+        let mut groups = Vec::with_capacity(NR_GROUP_TYPES);
+        for _ in 0..NR_GROUP_TYPES {
+            let group_size = xdrfile.read_count(4)?;
+            groups.push(
+                (0..group_size)
+                    .map(|_| xdrfile.read_i32().map(|x| x as usize))
+                    .collect::<Result<Vec<usize>, std::io::Error>>()?,
+            );
+        }
+
+        // read group names
+        let n_group_names = xdrfile.read_count(4)?;
+        let group_names = (0..n_group_names)
+            .map(|_| symbol_table.symstring(xdrfile))
+            .collect::<Result<Vec<String>, ParseTprError>>()?;
+
+        // read group numbers of atoms
+        let mut group_numbers = Vec::with_capacity(NR_GROUP_TYPES);
+        for _ in 0..NR_GROUP_TYPES {
+            let n_group_numbers = xdrfile.read_count(1)?;
+            group_numbers.push(
+                (0..n_group_numbers)
+                    .map(|_| xdrfile.read_uchar_body(tpr_version))
+                    .collect::<Result<Vec<u32>, std::io::Error>>()?,
+            );
+        }
+
+        Ok(SimulationGroups {
+            group_names,
+            groups,
+            group_numbers,
+        })
+    }
+}
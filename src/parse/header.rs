@@ -9,12 +9,26 @@ use crate::{
 };
 
 use super::xdr::XdrFile;
+use std::io::ErrorKind;
 
 impl TprHeader {
     /// Get `TprHeader` from a tpr file.
     pub(super) fn parse(xdrfile: &mut XdrFile) -> Result<TprHeader, ParseTprError> {
+        if xdrfile.remaining()? == 0 {
+            return Err(ParseTprError::EmptyFile);
+        }
+
         // get gromacs version used to write the tpr file
-        let gromacs_version = xdrfile.read_string_4byte()?;
+        // (files too short to contain the version string are not tpr files)
+        let gromacs_version = match xdrfile.read_string_4byte() {
+            Ok(x) => x,
+            Err(ParseTprError::CouldNotRead {
+                kind: ErrorKind::UnexpectedEof,
+                ..
+            }) => return Err(ParseTprError::NotTpr),
+            Err(ParseTprError::ImplausibleLength(_)) => return Err(ParseTprError::NotTpr),
+            Err(e) => return Err(e),
+        };
 
         // check that this is indeed a tpr file
         if !gromacs_version.contains("VERSION") {
@@ -32,7 +46,8 @@ impl TprHeader {
         let tpr_version = xdrfile.read_i32()?;
 
         // check that the version of the tpr file is supported
-        if tpr_version < 103 {
+        // (support for legacy versions is checked after reading the header)
+        if tpr_version < crate::MIN_LEGACY_VERSION {
             return Err(ParseTprError::UnsupportedVersion(tpr_version));
         }
 
@@ -40,6 +55,14 @@ impl TprHeader {
         let file_tag = xdrfile.read_string_4byte()?;
         let n_atoms = xdrfile.read_i32()?;
         let n_coupling_groups = xdrfile.read_i32()?;
+
+        // sanity check
+        for count in [n_atoms, n_coupling_groups] {
+            if count < 0 {
+                return Err(ParseTprError::ImplausibleLength(count as i64));
+            }
+        }
+
         let fep_state = xdrfile.read_i32()?;
         let lambda = xdrfile.read_real(precision)?;
 
@@ -3,51 +3,28 @@
 
 //! This file contains functions for parsing intramolecular and intermolecular interactions.
 
-use strum::IntoEnumIterator;
-
-use crate::{errors::ParseTprError, Atom, Bond};
-
-use super::{
-    ffparams::{FFParams, FTUpdater, InteractionType},
-    xdr::XdrFile,
+use crate::{
+    errors::ParseTprError,
+    structures::{AtomIndices, Interaction, InteractionType, Restraint, RestraintKind},
+    Atom, Bond,
 };
 
-/// Structure representing a intramolecular or an intermolecular interaction.
-#[derive(Debug, Clone)]
-pub(super) struct Interaction {
-    pub interaction_type: InteractionType,
-    pub interacting_atom_indices: Vec<i32>,
-}
+use super::{ffparams::FFParams, xdr::XdrFile};
 
 /// Read intramolecular or intermolecular interactions.
 pub(super) fn read_interactions(
     xdrfile: &mut XdrFile,
-    tpr_version: i32,
     ffparams: &FFParams,
 ) -> Result<Vec<Interaction>, ParseTprError> {
-    let updater = FTUpdater::default();
     let mut interactions = Vec::new();
 
-    for functype in InteractionType::iter() {
-        let mut read = true;
-
-        for (version, number) in &updater.update {
-            if tpr_version < *version && functype as i32 == *number {
-                read = false;
-                break;
-            }
-        }
-
-        if !read {
-            continue;
-        }
-
-        let number_of_instances = xdrfile.read_i32()?;
+    for &functype in ffparams.interaction_plan.iter() {
+        let number_of_instances = xdrfile.read_count(4)?;
         // get the number of atoms interacting via this interaction type
         let n_interacting_atoms = functype.n_interacting_atoms();
 
         // sanity check: the number of instances must be divisible by the number of atoms + 1
-        if number_of_instances % (n_interacting_atoms + 1) != 0 {
+        if number_of_instances % (n_interacting_atoms as usize + 1) != 0 {
             return Err(ParseTprError::InteractionDiscrepancy(functype as i32));
         }
 
@@ -59,6 +36,14 @@ pub(super) fn read_interactions(
     Ok(interactions)
 }
 
+/// Check that all interactions only involve atoms with index lower than `n_atoms`.
+pub(super) fn atoms_in_range(interactions: &[Interaction], n_atoms: usize) -> bool {
+    interactions
+        .iter()
+        .flat_map(|interaction| &interaction.interacting_atom_indices)
+        .all(|&index| index < n_atoms)
+}
+
 impl Interaction {
     /// Get `Interaction` from an `XdrFile`.
     fn parse(
@@ -79,15 +64,23 @@ impl Interaction {
             }
         };
 
-        let mut interacting_atom_indices = Vec::with_capacity(n_interacting_atoms as usize);
+        let n_interacting_atoms = n_interacting_atoms as usize;
+        if n_interacting_atoms > crate::MAX_INTERACTING_ATOMS {
+            return Err(ParseTprError::TooManyInteractingAtoms(n_interacting_atoms));
+        }
 
-        for _ in 0..n_interacting_atoms {
-            interacting_atom_indices.push(xdrfile.read_i32()?);
+        let mut indices = [0; crate::MAX_INTERACTING_ATOMS];
+        for index in indices.iter_mut().take(n_interacting_atoms) {
+            *index = usize::try_from(xdrfile.read_i32()?)
+                .map_err(|_| ParseTprError::CouldNotConstructTopology)?;
         }
 
         Ok(Interaction {
             interaction_type,
-            interacting_atom_indices,
+            interacting_atom_indices: AtomIndices {
+                len: n_interacting_atoms as u8,
+                indices,
+            },
         })
     }
 
@@ -112,9 +105,14 @@ impl Interaction {
     }
 
     /// Unpack SETTLE interaction into bonds.
+    /// Returns the two O-H bonds and, if `include_hh` is `true`, also the H-H bond.
     /// Returns an empty vector, if the interaction is not a settle.
     /// Returns `ParseTprError` if the bonds could not be constructed due to some inconsistency in the input data.
-    pub(super) fn settle2bonds(&self, atoms: &[Atom]) -> Result<Vec<Bond>, ParseTprError> {
+    pub(super) fn settle2bonds(
+        &self,
+        atoms: &[Atom],
+        include_hh: bool,
+    ) -> Result<Vec<Bond>, ParseTprError> {
         if !matches!(self.interaction_type, InteractionType::F_SETTLE) {
             return Ok(vec![]);
         }
@@ -129,21 +127,86 @@ impl Interaction {
         // get global atom indices
         let get_atom_index = |index: usize| -> Result<usize, ParseTprError> {
             atoms
-                .get(self.interacting_atom_indices[index] as usize)
+                .get(self.interacting_atom_indices[index])
                 .map(|x| (x.atom_number - 1) as usize)
                 .ok_or(ParseTprError::CouldNotConstructTopology)
         };
 
-        Ok(vec![
-            Bond {
-                atom1: get_atom_index(0)?,
-                atom2: get_atom_index(1)?,
-            },
-            Bond {
-                atom1: get_atom_index(0)?,
-                atom2: get_atom_index(2)?,
-            },
-        ])
+        let mut bonds = vec![
+            Bond::new(get_atom_index(0)?, get_atom_index(1)?),
+            Bond::new(get_atom_index(0)?, get_atom_index(2)?),
+        ];
+
+        if include_hh {
+            bonds.push(Bond::new(get_atom_index(1)?, get_atom_index(2)?));
+        }
+
+        Ok(bonds)
+    }
+
+    /// Unpack Urey-Bradley interaction into a 1-3 pair of its outer atoms.
+    /// Returns `None`, if the interaction is not a Urey-Bradley interaction.
+    /// Returns `ParseTprError` if the pair could not be constructed due to some inconsistency in the input data.
+    pub(super) fn unpack2urey_bradley(
+        &self,
+        atoms: &[Atom],
+    ) -> Result<Option<Bond>, ParseTprError> {
+        if !matches!(self.interaction_type, InteractionType::F_UREY_BRADLEY) {
+            return Ok(None);
+        }
+
+        match self.global_indices(atoms)?.as_slice() {
+            [atom1, _, atom3] => Ok(Some(Bond::new(*atom1, *atom3))),
+            _ => Err(ParseTprError::CouldNotConstructTopology),
+        }
+    }
+
+    /// Unpack CMAP interaction into the quintuple of the coupled atoms.
+    /// Returns `None`, if the interaction is not a CMAP interaction.
+    /// Returns `ParseTprError` if the quintuple could not be constructed due to some inconsistency in the input data.
+    pub(super) fn unpack2cmap(&self, atoms: &[Atom]) -> Result<Option<[usize; 5]>, ParseTprError> {
+        if !matches!(self.interaction_type, InteractionType::F_CMAP) {
+            return Ok(None);
+        }
+
+        self.global_indices(atoms)?
+            .try_into()
+            .map(Some)
+            .map_err(|_| ParseTprError::CouldNotConstructTopology)
+    }
+
+    /// Unpack distance, orientation, or dihedral restraint into a `Restraint`.
+    /// Returns `None`, if the interaction is not one of these restraints.
+    /// Returns `ParseTprError` if the restraint could not be constructed due to some inconsistency in the input data.
+    pub(super) fn unpack2restraint(
+        &self,
+        atoms: &[Atom],
+    ) -> Result<Option<Restraint>, ParseTprError> {
+        let kind = match self.interaction_type {
+            InteractionType::F_DISRES => RestraintKind::Distance,
+            InteractionType::F_ORIRES => RestraintKind::Orientation,
+            InteractionType::F_DIHRES => RestraintKind::Dihedral,
+            _ => return Ok(None),
+        };
+
+        Ok(Some(Restraint {
+            kind,
+            atoms: self.global_indices(atoms)?,
+        }))
+    }
+
+    /// Convert the local indices of the interacting atoms into global atom indices.
+    /// Returns `ParseTprError` if any of the atoms does not exist.
+    fn global_indices(&self, atoms: &[Atom]) -> Result<Vec<usize>, ParseTprError> {
+        self.interacting_atom_indices
+            .iter()
+            .map(|&index| {
+                atoms
+                    .get(index)
+                    .map(|x| (x.atom_number - 1) as usize)
+                    .ok_or(ParseTprError::CouldNotConstructTopology)
+            })
+            .collect()
     }
 
     /// Unpack `Interaction` into an Bond between specific atoms.
@@ -165,14 +228,11 @@ impl Interaction {
         // get global atom indices
         let get_atom_index = |index: usize| -> Result<usize, ParseTprError> {
             atoms
-                .get(self.interacting_atom_indices[index] as usize)
+                .get(self.interacting_atom_indices[index])
                 .map(|x| (x.atom_number - 1) as usize)
                 .ok_or(ParseTprError::CouldNotConstructTopology)
         };
 
-        Ok(Some(Bond {
-            atom1: get_atom_index(0)?,
-            atom2: get_atom_index(1)?,
-        }))
+        Ok(Some(Bond::new(get_atom_index(0)?, get_atom_index(1)?)))
     }
 }
@@ -4,17 +4,21 @@
 //! This file contains functions for parsing a tpr file.
 
 use crate::{
-    errors::ParseTprError,
-    structures::{Precision, SimBox, TprFile, TprHeader, TprTopology},
+    errors::{ParseTprError, ParseWarning},
+    structures::{Coordinates, ParseOptions, ParseStage, SimBox, TprFile, TprHeader, TprTopology},
+};
+use std::{
+    fs::File,
+    io::{BufReader, ErrorKind},
+    path::Path,
 };
-use coordinates::Coordinates;
-use std::{fs::File, io::BufReader, path::Path};
 use xdr::XdrFile;
 
-use self::{ffparams::FFParams, symtab::SymTable};
+use self::{ffparams::FFParams, symtab::SymTable, topology::ParsedTopology};
 
 pub mod coordinates;
 pub mod ffparams;
+pub mod groups;
 pub mod header;
 pub mod interactions;
 pub mod molblocks;
@@ -25,57 +29,242 @@ pub mod topology;
 pub mod xdr;
 
 /// Parse a file in a Gromacs TPR format.
-pub(crate) fn parse_tpr(filename: impl AsRef<Path>) -> Result<TprFile, ParseTprError> {
+pub(crate) fn parse_tpr(
+    filename: impl AsRef<Path>,
+    options: &ParseOptions,
+    progress: &mut dyn FnMut(ParseStage),
+    warnings: &mut Vec<ParseWarning>,
+) -> Result<TprFile, ParseTprError> {
     let file = match File::open(filename.as_ref()) {
         Ok(x) => x,
         Err(_) => return Err(ParseTprError::CouldNotOpen(Box::from(filename.as_ref()))),
     };
 
-    let reader = BufReader::new(file);
-    let mut xdrfile = XdrFile::new(reader);
+    parse_xdr(
+        XdrFile::new(BufReader::new(file)),
+        options,
+        progress,
+        warnings,
+    )
+}
+
+/// Parse bytes in memory containing a tpr file in a Gromacs TPR format.
+pub(crate) fn parse_tpr_bytes(
+    bytes: &[u8],
+    options: &ParseOptions,
+    progress: &mut dyn FnMut(ParseStage),
+    warnings: &mut Vec<ParseWarning>,
+) -> Result<TprFile, ParseTprError> {
+    parse_xdr(XdrFile::from_bytes(bytes), options, progress, warnings)
+}
 
+/// Parse a tpr file from an `XdrFile` source.
+fn parse_xdr(
+    mut xdrfile: XdrFile,
+    options: &ParseOptions,
+    progress: &mut dyn FnMut(ParseStage),
+    warnings: &mut Vec<ParseWarning>,
+) -> Result<TprFile, ParseTprError> {
     // read header of the tpr file
     let header = TprHeader::parse(&mut xdrfile)?;
+    progress(ParseStage::HeaderDone);
+
+    // refuse to parse legacy files, unless requested
+    if header.tpr_version < crate::MIN_SUPPORTED_VERSION && !options.allow_legacy {
+        return Err(ParseTprError::UnsupportedVersion(header.tpr_version));
+    }
+
+    if header.tpr_version < crate::MIN_SUPPORTED_VERSION {
+        warnings.push(ParseWarning::LegacyVersion(header.tpr_version));
+    }
+
+    // refuse to parse files of unknown format
+    if header.tpr_version > crate::MAX_TESTED_VERSION && !options.allow_untested_version {
+        return Err(ParseTprError::UntestedVersion(header.tpr_version));
+    }
+
+    if header.tpr_version > crate::MAX_TESTED_VERSION {
+        warnings.push(ParseWarning::UntestedVersion(header.tpr_version));
+    }
+
+    // number of bytes following the header
+    let body_start = xdrfile.position()?;
+    let available = xdrfile.remaining()?;
+
+    // if the size of the body is known, check that the file is complete
+    if let Some(body_size) = header.body_size.and_then(|size| u64::try_from(size).ok()) {
+        if available < body_size {
+            return Err(ParseTprError::Truncated {
+                parsed_header: Box::new(header),
+                kind: ErrorKind::UnexpectedEof,
+                message: String::from("file is shorter than specified in its header"),
+            });
+        }
+    }
+
+    // read the rest of the file; if the file ends prematurely, return the parsed header
+    let body = match parse_body(&mut xdrfile, &header, options, progress, warnings) {
+        Ok(x) => x,
+        Err(ParseTprError::CouldNotRead {
+            kind: ErrorKind::UnexpectedEof,
+            message,
+        }) => {
+            return Err(ParseTprError::Truncated {
+                parsed_header: Box::new(header),
+                kind: ErrorKind::UnexpectedEof,
+                message,
+            })
+        }
+        Err(e) => return Err(e),
+    };
+
+    check_body_size(&header, xdrfile.position()? - body_start, available)?;
+
+    Ok(TprFile {
+        header,
+        system_name: body.system_name,
+        simbox: body.simbox,
+        topology: body.parsed.topology,
+        molecule_blocks: body.parsed.molecule_blocks,
+        groups: body.parsed.groups,
+        symbol_table: body.symbol_table,
+        molecule_types: body.parsed.molecule_types,
+        intermolecular_interactions: body.parsed.intermolecular_interactions,
+        coordinates: body.coordinates,
+    })
+}
+
+/// Structure holding the data parsed from the body of the tpr file.
+struct ParsedBody {
+    /// Name of the system.
+    system_name: String,
+    /// Simulation box.
+    simbox: Option<SimBox>,
+    /// Topology, molecule blocks, and atom groups.
+    parsed: ParsedTopology,
+    /// Symbol table (only if requested).
+    symbol_table: Option<Vec<String>>,
+    /// Contiguous blocks of coordinates (only if requested).
+    coordinates: Option<Coordinates>,
+}
 
+/// Check that the size of the body stored in the header of the tpr file is consistent
+/// with the number of bytes consumed by the parser and with the size of the file.
+///
+/// The parser does not read the input record which is placed at the end of the body,
+/// so the number of consumed bytes can be smaller than the size of the body.
+/// Gromacs pads the body so that its size in the file is a multiple of 4 bytes.
+fn check_body_size(header: &TprHeader, consumed: u64, available: u64) -> Result<(), ParseTprError> {
+    let expected = match header.body_size {
+        Some(x) => x,
+        None => return Ok(()),
+    };
+
+    if consumed as i64 > expected {
+        return Err(ParseTprError::BodySizeMismatch {
+            expected,
+            actual: consumed as i64,
+        });
+    }
+
+    let padded = match expected % 4 {
+        0 => expected,
+        x => expected + 4 - x,
+    };
+
+    if available as i64 != padded {
+        return Err(ParseTprError::BodySizeMismatch {
+            expected,
+            actual: available as i64,
+        });
+    }
+
+    Ok(())
+}
+
+/// Parse the body of the tpr file, i.e., everything following the header.
+fn parse_body(
+    xdrfile: &mut XdrFile,
+    header: &TprHeader,
+    options: &ParseOptions,
+    progress: &mut dyn FnMut(ParseStage),
+    warnings: &mut Vec<ParseWarning>,
+) -> Result<ParsedBody, ParseTprError> {
     // read simulation box (if present)
     let simbox = if header.has_box {
-        Some(SimBox::parse(&mut xdrfile, header.precision)?)
+        Some(SimBox::parse(xdrfile, header.precision)?)
     } else {
         None
     };
 
     // skip some data that used to be temperature coupling information
-    let jump = match header.precision {
-        Precision::Single => 4 * header.n_coupling_groups,
-        Precision::Double => 8 * header.n_coupling_groups,
-    };
-    xdrfile.jump(jump as i64)?;
+    xdrfile.skip_multiple_reals(header.precision, header.n_coupling_groups as i64)?;
 
     // read symbol table
-    let symtab = SymTable::parse(&mut xdrfile, header.tpr_version)?;
+    let symtab = SymTable::parse(xdrfile, header.tpr_version)?;
+    progress(ParseStage::SymtabDone);
 
-    // get system name
-    let system_name = symtab.symstring(&mut xdrfile)?;
+    // get system name (without trailing whitespace and control characters)
+    let mut system_name = symtab.symstring(xdrfile)?;
+    let trimmed_len = system_name
+        .trim_end_matches(|c: char| c.is_whitespace() || c.is_control())
+        .len();
+    system_name.truncate(trimmed_len);
 
     // get force-field parameters
-    let ffparams = FFParams::parse(&mut xdrfile, header.precision, header.tpr_version)?;
-
-    let mut top = TprTopology::parse(
-        &mut xdrfile,
-        header.precision,
-        header.tpr_version,
-        &symtab,
-        &ffparams,
-        header.n_atoms,
+    let ffparams = FFParams::parse(xdrfile, header.precision, header.tpr_version)?;
+    progress(ParseStage::FFParamsDone);
+
+    if ffparams.n_renumbered > 0 {
+        warnings.push(ParseWarning::RenumberedInteractionTypes(
+            ffparams.n_renumbered,
+        ));
+    }
+
+    let mut parsed = TprTopology::parse(
+        xdrfile, header, &symtab, &ffparams, options, progress, warnings,
     )?;
 
     // get positions, velocities, and forces
-    top.fill_with_coordinates(Coordinates::parse(&mut xdrfile, &header)?);
+    let coordinates = if !options.skip_coordinates {
+        let coordinates = Coordinates::parse(xdrfile, header)?;
+        parsed.topology.fill_with_coordinates(&coordinates);
+        progress(ParseStage::CoordinatesDone);
+        options.keep_coordinate_blocks.then_some(coordinates)
+    } else {
+        None
+    };
 
-    Ok(TprFile {
-        header,
+    if options.skip_bstate {
+        for atom in parsed.topology.atoms.iter_mut() {
+            atom.mass_b = None;
+            atom.charge_b = None;
+        }
+    }
+
+    if options.fill_missing_elements {
+        parsed.topology.fill_missing_elements();
+    }
+
+    if options.strict {
+        parsed.topology.validate()?;
+    }
+
+    let n_unknown = parsed
+        .topology
+        .atoms
+        .iter()
+        .filter(|atom| atom.element.is_none() && !atom.is_virtual_site())
+        .count();
+    if n_unknown > 0 {
+        warnings.push(ParseWarning::UnknownElements(n_unknown));
+    }
+
+    Ok(ParsedBody {
         system_name,
         simbox,
-        topology: top,
+        parsed,
+        symbol_table: options.keep_symbol_table.then_some(symtab.symbols),
+        coordinates,
     })
 }
@@ -5,7 +5,7 @@
 
 use crate::{
     errors::ParseTprError,
-    structures::{Atom, Bond, Precision},
+    structures::{MolBlockView, Precision, TprTopology},
 };
 
 use super::{moltypes::MoleculeType, xdr::XdrFile};
@@ -15,6 +15,9 @@ use super::{moltypes::MoleculeType, xdr::XdrFile};
 pub(super) struct MolBlock {
     pub molecule_type: i32,
     pub n_molecules: i32,
+    pub atoms_per_molecule: usize,
+    pub posres_count: usize,
+    pub fbposres_count: usize,
 }
 
 impl MolBlock {
@@ -25,42 +28,76 @@ impl MolBlock {
     ) -> Result<Self, ParseTprError> {
         let molecule_type = xdrfile.read_i32()?;
         let n_molecules = xdrfile.read_i32()?;
-        // ignore n_atoms_per_molecule field
-        xdrfile.jump(4)?;
+        let atoms_per_molecule = xdrfile.read_count(0)?;
 
-        // skip position restraints
-        for _ in 0..2 {
-            let n_posres = xdrfile.read_i32()?;
+        // skip reference positions of the position restraints and flat-bottomed position restraints
+        let mut skip_posres = || -> Result<usize, ParseTprError> {
+            let n_posres = xdrfile.read_count(crate::DIM as u64 * 4)?;
             xdrfile.skip_multiple_reals(precision, crate::DIM as i64 * n_posres as i64)?;
-        }
+            Ok(n_posres)
+        };
+
+        let posres_count = skip_posres()?;
+        let fbposres_count = skip_posres()?;
 
         Ok(MolBlock {
             molecule_type,
             n_molecules,
+            atoms_per_molecule,
+            posres_count,
+            fbposres_count,
+        })
+    }
+
+    /// Convert `MolBlock` to a public `MolBlockView`.
+    pub(super) fn view(
+        &self,
+        molecule_types: &[MoleculeType],
+    ) -> Result<MolBlockView, ParseTprError> {
+        let moltype = match molecule_types.get(self.molecule_type as usize) {
+            Some(x) => x,
+            None => return Err(ParseTprError::CouldNotConstructTopology),
+        };
+
+        Ok(MolBlockView {
+            molecule_type: self.molecule_type as usize,
+            name: moltype.name.clone(),
+            n_molecules: self.n_molecules as usize,
+            atoms_per_molecule: self.atoms_per_molecule,
+            posres_count: self.posres_count,
+            fbposres_count: self.fbposres_count,
         })
     }
 
-    /// Unpack `MolBlock` to molecules, i.e., a vector of atoms and a vector of bonds.
+    /// Unpack `MolBlock` to molecules and add them into the topology.
     pub(super) fn unpack2molecules(
         &self,
+        topology: &mut TprTopology,
         molecule_types: &[MoleculeType],
         atom_counter: &mut i32,
         residue_counter: &mut i32,
-    ) -> Result<(Vec<Atom>, Vec<Bond>), ParseTprError> {
+        molecule_counter: &mut usize,
+        settle_include_hh: bool,
+    ) -> Result<(), ParseTprError> {
         let moltype = match molecule_types.get(self.molecule_type as usize) {
             Some(x) => x,
             None => return Err(ParseTprError::CouldNotConstructTopology),
         };
 
-        let mut atoms = Vec::with_capacity(moltype.atoms.len() * self.n_molecules as usize);
-        let mut bonds = Vec::new();
+        topology
+            .atoms
+            .reserve(moltype.atoms.len() * self.n_molecules as usize);
 
         for _ in 0..self.n_molecules {
-            let (new_atoms, new_bonds) = moltype.unpack2molecule(atom_counter, residue_counter)?;
-            atoms.extend(new_atoms);
-            bonds.extend(new_bonds);
+            topology.append(moltype.unpack2molecule(
+                atom_counter,
+                residue_counter,
+                *molecule_counter,
+                settle_include_hh,
+            )?);
+            *molecule_counter += 1;
         }
 
-        Ok((atoms, bonds))
+        Ok(())
     }
 }
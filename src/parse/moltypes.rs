@@ -3,23 +3,22 @@
 
 //! This file contains functions for obtaining molecule types from TPR file.
 
-use mendeleev::Element;
-
 use crate::{
+    atom,
     errors::ParseTprError,
     parse::xdr::XdrFile,
-    structures::{Atom, Bond, Precision},
+    structures::{
+        Atom, Element, Interaction, MoleculeTypeAtomView, MoleculeTypeResidueView,
+        MoleculeTypeView, Precision, TprTopology,
+    },
 };
 
-use super::{
-    ffparams::FFParams,
-    interactions::{self, Interaction},
-    symtab::SymTable,
-};
+use super::{ffparams::FFParams, interactions, symtab::SymTable};
 
 /// Structure representing Molecule Type.
 #[derive(Debug, Clone)]
 pub(super) struct MoleculeType {
+    pub name: String,
     pub atoms: Vec<MoleculeTypeAtom>,
     pub residues: Vec<MoleculeTypeResidue>,
     pub interactions: Vec<Interaction>,
@@ -31,6 +30,8 @@ pub(super) struct MoleculeTypeAtom {
     pub name: String,
     pub mass: f64,
     pub charge: f64,
+    pub mass_b: Option<f64>,
+    pub charge_b: Option<f64>,
     pub residue_index: i32,
     pub element: Option<Element>,
 }
@@ -40,6 +41,7 @@ pub(super) struct MoleculeTypeAtom {
 pub(super) struct MoleculeTypeResidue {
     pub name: String,
     pub number: i32,
+    pub insertion_code: Option<char>,
 }
 
 impl MoleculeType {
@@ -50,17 +52,18 @@ impl MoleculeType {
         tpr_version: i32,
         symbol_table: &SymTable,
         ffparams: &FFParams,
+        trim_names: bool,
     ) -> Result<Self, ParseTprError> {
-        // skip the name of the molecule type
-        symbol_table.symstring(xdrfile)?;
+        // get the name of the molecule type
+        let name = symbol_table.symstring(xdrfile)?;
 
         // get the number of atoms and residues in the molecule type
-        let n_atoms = xdrfile.read_i32()?;
-        let n_residues = xdrfile.read_i32()?;
+        let n_atoms = xdrfile.read_count(4)?;
+        let n_residues = xdrfile.read_count(4)?;
 
         // read atoms
-        let mut atoms = Vec::with_capacity(n_atoms as usize);
-        for _ in 0..(n_atoms as usize) {
+        let mut atoms = Vec::with_capacity(n_atoms);
+        for _ in 0..n_atoms {
             atoms.push(MoleculeTypeAtom::parse(xdrfile, precision, tpr_version)?);
         }
 
@@ -76,8 +79,8 @@ impl MoleculeType {
         }
 
         // read residues
-        let mut residues = Vec::with_capacity(n_residues as usize);
-        for _ in 0..(n_residues as usize) {
+        let mut residues = Vec::with_capacity(n_residues);
+        for _ in 0..n_residues {
             residues.push(MoleculeTypeResidue::parse(
                 xdrfile,
                 tpr_version,
@@ -85,8 +88,19 @@ impl MoleculeType {
             )?);
         }
 
+        // all atoms must belong to one of the residues
+        if atoms
+            .iter()
+            .any(|atom| atom.residue_index < 0 || atom.residue_index as usize >= n_residues)
+        {
+            return Err(ParseTprError::CouldNotConstructTopology);
+        }
+
         // read interactions
-        let interactions = interactions::read_interactions(xdrfile, tpr_version, ffparams)?;
+        let interactions = interactions::read_interactions(xdrfile, ffparams)?;
+        if !interactions::atoms_in_range(&interactions, n_atoms) {
+            return Err(ParseTprError::CouldNotConstructTopology);
+        }
 
         // skip block indices
         let n_blocks = xdrfile.read_i32()?;
@@ -98,44 +112,102 @@ impl MoleculeType {
         xdrfile.jump(4 * n_exclusions as i64 + 4)?;
         xdrfile.jump(4 * n_excluded as i64)?;
 
-        Ok(MoleculeType {
+        let mut moltype = MoleculeType {
+            name,
             atoms,
             residues,
             interactions,
-        })
+        };
+
+        if trim_names {
+            moltype.trim_names();
+        }
+
+        Ok(moltype)
     }
 
-    /// Unpack `MoleculeType` to molecule, i.e., a vector of atoms and a vector of bonds.
+    /// Remove leading and trailing ASCII whitespace from the names of the molecule type,
+    /// its atoms, and its residues.
+    fn trim_names(&mut self) {
+        let trim = |name: &mut String| {
+            let trimmed = name.trim_matches(|c: char| c.is_ascii_whitespace());
+            if trimmed.len() != name.len() {
+                *name = trimmed.to_owned();
+            }
+        };
+
+        trim(&mut self.name);
+        self.atoms.iter_mut().for_each(|atom| trim(&mut atom.name));
+        self.residues
+            .iter_mut()
+            .for_each(|residue| trim(&mut residue.name));
+    }
+
+    /// Convert `MoleculeType` to a public `MoleculeTypeView`.
+    pub(super) fn into_view(self) -> MoleculeTypeView {
+        MoleculeTypeView {
+            name: self.name,
+            atoms: self
+                .atoms
+                .into_iter()
+                .map(|atom| MoleculeTypeAtomView {
+                    name: atom.name,
+                    mass: atom.mass,
+                    charge: atom.charge,
+                    mass_b: atom.mass_b,
+                    charge_b: atom.charge_b,
+                    residue_index: atom.residue_index as usize,
+                    element: atom.element,
+                })
+                .collect(),
+            residues: self
+                .residues
+                .into_iter()
+                .map(|residue| MoleculeTypeResidueView {
+                    name: residue.name,
+                    number: residue.number,
+                    insertion_code: residue.insertion_code,
+                })
+                .collect(),
+            interactions: self.interactions,
+        }
+    }
+
+    /// Unpack `MoleculeType` to molecule, i.e., a topology containing the atoms,
+    /// bonds, and other connectivity of a single molecule.
     pub(super) fn unpack2molecule(
         &self,
         atom_counter: &mut i32,
         residue_counter: &mut i32,
-    ) -> Result<(Vec<Atom>, Vec<Bond>), ParseTprError> {
+        molecule_id: usize,
+        settle_include_hh: bool,
+    ) -> Result<TprTopology, ParseTprError> {
         let mut atoms = Vec::with_capacity(self.atoms.len());
 
-        let mut previous_residue_number = None;
+        let mut previous_residue = None;
         for moltype_atom in &self.atoms {
             atoms.push(moltype_atom.convert2atom(
                 &self.residues,
                 atom_counter,
                 residue_counter,
-                &mut previous_residue_number,
+                &mut previous_residue,
+                molecule_id,
             )?)
         }
 
-        let mut bonds = Vec::new();
+        let mut molecule = TprTopology {
+            atoms,
+            bonds: Vec::new(),
+            urey_bradley_pairs: Vec::new(),
+            cmap_terms: Vec::new(),
+            restraints: Vec::new(),
+        };
+
         for interaction in self.interactions.iter() {
-            match interaction.unpack2bond(&atoms) {
-                Ok(Some(x)) => bonds.push(x),
-                Ok(None) => match interaction.settle2bonds(&atoms) {
-                    Ok(x) => bonds.extend(x.into_iter()),
-                    Err(e) => return Err(e),
-                },
-                Err(e) => return Err(e),
-            }
+            molecule.add_interaction(interaction, settle_include_hh)?;
         }
 
-        Ok((atoms, bonds))
+        Ok(molecule)
     }
 }
 
@@ -149,8 +221,11 @@ impl MoleculeTypeAtom {
         let mass = xdrfile.read_real(precision)?;
         let charge = xdrfile.read_real(precision)?;
 
-        // ignore mass_b and charge_b
-        xdrfile.skip_multiple_reals(precision, 2)?;
+        // B-state values are only stored if they differ from the A-state values
+        let mass_b = xdrfile.read_real(precision)?;
+        let mass_b = (mass_b != mass).then_some(mass_b);
+        let charge_b = xdrfile.read_real(precision)?;
+        let charge_b = (charge_b != charge).then_some(charge_b);
 
         // skip both atom type indices
         xdrfile.read_ushort_body(tpr_version)?;
@@ -161,12 +236,14 @@ impl MoleculeTypeAtom {
         let residue_index = xdrfile.read_i32()?;
 
         let atomic_number = xdrfile.read_i32()?;
-        let element = from_atom_number(atomic_number);
+        let element = atom::from_atom_number(atomic_number);
 
         Ok(MoleculeTypeAtom {
             name: String::from("Unknown"),
             mass,
             charge,
+            mass_b,
+            charge_b,
             residue_index,
             element,
         })
@@ -178,22 +255,21 @@ impl MoleculeTypeAtom {
         residues: &[MoleculeTypeResidue],
         atom_counter: &mut i32,
         residue_counter: &mut i32,
-        previous_residue_number: &mut Option<i32>,
+        previous_residue: &mut Option<(i32, i32)>,
+        molecule_id: usize,
     ) -> Result<Atom, ParseTprError> {
         let residue = match residues.get(self.residue_index as usize) {
             Some(x) => x,
             None => return Err(ParseTprError::CouldNotConstructTopology),
         };
 
-        // increase the residue counter, if new residue is encountered
-        if let Some(unwrapped) = previous_residue_number {
-            if *unwrapped != residue.number {
-                *residue_counter += 1;
-                *previous_residue_number = Some(residue.number);
-            }
-        } else {
+        // increase the residue counter, if new residue is encountered;
+        // residue numbers stored in the tpr file may wrap around or repeat in large systems,
+        // so the index of the residue in the molecule type is compared as well
+        let current_residue = (self.residue_index, residue.number);
+        if *previous_residue != Some(current_residue) {
             *residue_counter += 1;
-            *previous_residue_number = Some(residue.number);
+            *previous_residue = Some(current_residue);
         }
 
         *atom_counter += 1;
@@ -203,12 +279,18 @@ impl MoleculeTypeAtom {
             atom_number: *atom_counter - 1,
             residue_name: residue.name.clone(),
             residue_number: *residue_counter,
+            original_residue_number: residue.number,
             mass: self.mass,
             charge: self.charge,
+            mass_b: self.mass_b,
+            charge_b: self.charge_b,
             element: self.element,
             position: None,
             velocity: None,
             force: None,
+            molecule_id,
+            chain_id: ' ',
+            insertion_code: residue.insertion_code,
         })
     }
 }
@@ -223,15 +305,16 @@ impl MoleculeTypeResidue {
         let name = symbol_table.symstring(xdrfile)?;
         let number = xdrfile.read_i32()?;
 
-        // skip insertion code
-        xdrfile.read_uchar_body(tpr_version)?;
+        // both zero byte and space mean that the residue has no insertion code
+        let insertion_code = match xdrfile.read_uchar_body(tpr_version)? as u8 {
+            0 | b' ' => None,
+            code => Some(char::from(code)),
+        };
 
-        Ok(MoleculeTypeResidue { name, number })
+        Ok(MoleculeTypeResidue {
+            name,
+            number,
+            insertion_code,
+        })
     }
 }
-
-/// Attempt to identify element from atomic number.
-/// Returns `None` if the atomic number corresponds to no element.
-fn from_atom_number(atomic_number: i32) -> Option<Element> {
-    Element::list().get(atomic_number as usize - 1).copied()
-}
@@ -15,10 +15,11 @@ pub(super) struct SymTable {
 impl SymTable {
     /// Get `SymTable` from `XdrFile`.
     pub(super) fn parse(xdrfile: &mut XdrFile, tpr_version: i32) -> Result<Self, ParseTprError> {
-        let symtab_len = xdrfile.read_i32()?;
+        // each string occupies at least 8 bytes
+        let symtab_len = xdrfile.read_count(8)?;
 
         let mut symtab = SymTable {
-            symbols: Vec::with_capacity(symtab_len as usize),
+            symbols: Vec::with_capacity(symtab_len),
         };
 
         for _ in 0..symtab_len {
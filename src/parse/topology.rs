@@ -3,32 +3,51 @@
 
 //! This file contains functions for obtaining system topology from a TPR file.
 
-use super::{
-    coordinates::Coordinates, ffparams::FFParams, interactions::Interaction, molblocks::MolBlock,
-    moltypes::MoleculeType, xdr::XdrFile,
-};
+use super::{ffparams::FFParams, molblocks::MolBlock, moltypes::MoleculeType, xdr::XdrFile};
 use crate::{
-    errors::ParseTprError,
-    structures::{Precision, TprTopology},
-    NR_GROUP_TYPES,
+    errors::{ParseTprError, ParseWarning},
+    structures::{
+        Atom, Coordinates, Interaction, MolBlockView, MoleculeTypeView, ParseOptions, ParseStage,
+        SimulationGroups, TprHeader, TprTopology,
+    },
 };
 
 use super::symtab::SymTable;
 
+/// Structure holding the data parsed from the topology section of the tpr file.
+#[derive(Debug, Clone)]
+pub(super) struct ParsedTopology {
+    /// System topology.
+    pub(super) topology: TprTopology,
+    /// Molecule blocks of the system.
+    pub(super) molecule_blocks: Vec<MolBlockView>,
+    /// Atom groups used by the simulation.
+    pub(super) groups: SimulationGroups,
+    /// Molecule types of the system.
+    pub(super) molecule_types: Vec<MoleculeTypeView>,
+    /// Intermolecular interactions.
+    pub(super) intermolecular_interactions: Vec<Interaction>,
+}
+
 impl TprTopology {
-    /// Get system topology from the tpr file.
+    /// Get system topology, molecule blocks, and atom groups from the tpr file.
     pub(super) fn parse(
         xdrfile: &mut XdrFile,
-        precision: Precision,
-        tpr_version: i32,
+        header: &TprHeader,
         symbol_table: &SymTable,
         ffparams: &FFParams,
-        expected_n_atoms: i32,
-    ) -> Result<Self, ParseTprError> {
+        options: &ParseOptions,
+        progress: &mut dyn FnMut(ParseStage),
+        warnings: &mut Vec<ParseWarning>,
+    ) -> Result<ParsedTopology, ParseTprError> {
+        let precision = header.precision;
+        let tpr_version = header.tpr_version;
+        let expected_n_atoms = header.n_atoms;
+
         // get molecule types
-        let n_moltypes = xdrfile.read_i32()?;
+        let n_moltypes = xdrfile.read_count(4)?;
 
-        let mut molecule_types = Vec::with_capacity(n_moltypes as usize);
+        let mut molecule_types = Vec::with_capacity(n_moltypes);
         for _ in 0..n_moltypes {
             molecule_types.push(MoleculeType::parse(
                 xdrfile,
@@ -36,13 +55,14 @@ impl TprTopology {
                 tpr_version,
                 symbol_table,
                 ffparams,
+                options.trim_names,
             )?);
         }
 
         // get molecule blocks
-        let n_molblocks = xdrfile.read_i32()?;
+        let n_molblocks = xdrfile.read_count(4)?;
 
-        let mut molecule_blocks = Vec::with_capacity(n_molblocks as usize);
+        let mut molecule_blocks = Vec::with_capacity(n_molblocks);
         for _ in 0..n_molblocks {
             molecule_blocks.push(MolBlock::parse(xdrfile, precision)?)
         }
@@ -50,20 +70,53 @@ impl TprTopology {
         // read the number of atoms for sanity checking
         let n_atoms = xdrfile.read_i32()?;
 
-        // read intermolecular interactions
-        let intermolecular = if xdrfile.read_bool_body(tpr_version)? {
-            Some(super::interactions::read_interactions(
-                xdrfile,
-                tpr_version,
-                ffparams,
-            )?)
+        // read intermolecular interactions (only present since version 103)
+        let intermolecular = if tpr_version >= crate::MIN_SUPPORTED_VERSION
+            && xdrfile.read_bool_body(tpr_version)?
+        {
+            Some(super::interactions::read_interactions(xdrfile, ffparams)?)
         } else {
             None
         };
 
+        if let Some(interactions) = &intermolecular {
+            if !super::interactions::atoms_in_range(interactions, expected_n_atoms as usize) {
+                return Err(ParseTprError::CouldNotConstructTopology);
+            }
+        }
+
+        // keep the information about the molecule blocks
+        let views = molecule_blocks
+            .iter()
+            .map(|block| block.view(&molecule_types))
+            .collect::<Result<Vec<MolBlockView>, ParseTprError>>()?;
+
+        // check the number of atoms in the molecule blocks before constructing the topology
+        // (prevents excessive allocations for corrupted files)
+        let n_atoms_blocks = views
+            .iter()
+            .map(|view| {
+                (molecule_types[view.molecule_type].atoms.len() as u64)
+                    .saturating_mul(view.n_molecules as u64)
+            })
+            .fold(0u64, |acc, x| acc.saturating_add(x));
+
+        if n_atoms_blocks != expected_n_atoms as u64 {
+            return Err(ParseTprError::InconsistentNumberOfAtoms(
+                expected_n_atoms,
+                i32::try_from(n_atoms_blocks).unwrap_or(i32::MAX),
+            ));
+        }
+
         // construct the topology from the molecule types, molecule blocks and intermolecular interactions
-        let topology =
-            TprTopology::construct_topology(molecule_blocks, molecule_types, intermolecular)?;
+        let topology = TprTopology::construct_topology(
+            molecule_blocks,
+            &molecule_types,
+            intermolecular.as_deref(),
+            options,
+            progress,
+            warnings,
+        )?;
 
         // check that the number of atoms is consistent
         if n_atoms != expected_n_atoms {
@@ -91,26 +144,17 @@ impl TprTopology {
         }
 
         // skip dihedral correction maps
-        let n_grids = xdrfile.read_i32()?;
-        let grid_spacing = xdrfile.read_i32()?;
+        let n_grids = xdrfile.read_count(0)? as i64;
+        let grid_spacing = xdrfile.read_count(0)? as i64;
         xdrfile.skip_multiple_reals(
             precision,
-            (4 * n_grids * grid_spacing * grid_spacing) as i64,
+            4i64.saturating_mul(n_grids)
+                .saturating_mul(grid_spacing)
+                .saturating_mul(grid_spacing),
         )?;
 
-        // skip atom groups
-        for _ in 0..NR_GROUP_TYPES {
-            let group_size = xdrfile.read_i32()?;
-            xdrfile.jump(4 * group_size as i64)?;
-        }
-
-        let n_group_names = xdrfile.read_i32()?;
-        xdrfile.jump(4 * n_group_names as i64)?;
-
-        for _ in 0..NR_GROUP_TYPES {
-            let n_group_numbers = xdrfile.read_i32()?;
-            xdrfile.skip_multiple_uchars_body(tpr_version, n_group_numbers as i64)?;
-        }
+        // read atom groups
+        let groups = SimulationGroups::parse(xdrfile, tpr_version, symbol_table)?;
 
         // skip exclusions
         if tpr_version >= 120 {
@@ -121,62 +165,150 @@ impl TprTopology {
                 ));
             }
 
-            xdrfile.jump(4 * intermolecular_exclusion_group_size)?;
+            xdrfile.jump(intermolecular_exclusion_group_size.saturating_mul(4))?;
         }
 
-        Ok(topology)
+        Ok(ParsedTopology {
+            topology,
+            molecule_blocks: views,
+            groups,
+            molecule_types: molecule_types
+                .into_iter()
+                .map(MoleculeType::into_view)
+                .collect(),
+            intermolecular_interactions: intermolecular.unwrap_or_default(),
+        })
     }
 
     /// Construct the final topology from molecule blocks, molecule types and intermolecular interactions.
     fn construct_topology(
         molecule_blocks: Vec<MolBlock>,
-        molecule_types: Vec<MoleculeType>,
-        intermolecular: Option<Vec<Interaction>>,
+        molecule_types: &[MoleculeType],
+        intermolecular: Option<&[Interaction]>,
+        options: &ParseOptions,
+        progress: &mut dyn FnMut(ParseStage),
+        warnings: &mut Vec<ParseWarning>,
     ) -> Result<TprTopology, ParseTprError> {
-        let mut atoms = Vec::new();
-        let mut bonds = Vec::new();
+        let mut topology = TprTopology {
+            atoms: Vec::new(),
+            bonds: Vec::new(),
+            urey_bradley_pairs: Vec::new(),
+            cmap_terms: Vec::new(),
+            restraints: Vec::new(),
+        };
         let mut atom_counter = 1;
         let mut residue_counter = 0;
+        let mut molecule_counter = 0;
 
         for molblock in molecule_blocks {
-            let (new_atoms, new_bonds) = molblock.unpack2molecules(
-                &molecule_types,
+            molblock.unpack2molecules(
+                &mut topology,
+                molecule_types,
                 &mut atom_counter,
                 &mut residue_counter,
+                &mut molecule_counter,
+                options.settle_include_hh,
             )?;
-
-            atoms.extend(new_atoms);
-            bonds.extend(new_bonds);
+            progress(ParseStage::TopologyDone(topology.atoms.len()));
         }
 
+        assign_chains(&mut topology.atoms);
+
         // convert intermolecular interactions to bonds
         if let Some(inter) = intermolecular {
             for interaction in inter.iter() {
-                if let Some(bond) = interaction.unpack2bond(&atoms)? {
-                    bonds.push(bond);
-                }
+                topology.add_interaction(interaction, options.settle_include_hh)?;
             }
         }
 
-        Ok(TprTopology { atoms, bonds })
+        if options.deduplicate_bonds {
+            let n_bonds = topology.bonds.len();
+            topology.deduplicate_bonds();
+            let n_removed = n_bonds - topology.bonds.len();
+            if n_removed > 0 {
+                warnings.push(ParseWarning::DuplicateBondsRemoved(n_removed));
+            }
+        }
+
+        if options.sort_bonds {
+            topology.sort_bonds();
+        }
+
+        Ok(topology)
+    }
+
+    /// Add the connectivity described by an interaction into the topology.
+    /// The atoms involved in the interaction must already be part of the topology.
+    /// If `settle_include_hh` is `true`, SETTLE interactions also produce the H-H bond.
+    pub(super) fn add_interaction(
+        &mut self,
+        interaction: &Interaction,
+        settle_include_hh: bool,
+    ) -> Result<(), ParseTprError> {
+        if let Some(bond) = interaction.unpack2bond(&self.atoms)? {
+            self.bonds.push(bond);
+        } else if let Some(pair) = interaction.unpack2urey_bradley(&self.atoms)? {
+            self.urey_bradley_pairs.push(pair);
+        } else if let Some(quintuple) = interaction.unpack2cmap(&self.atoms)? {
+            self.cmap_terms.push(quintuple);
+        } else if let Some(restraint) = interaction.unpack2restraint(&self.atoms)? {
+            self.restraints.push(restraint);
+        } else {
+            self.bonds
+                .extend(interaction.settle2bonds(&self.atoms, settle_include_hh)?);
+        }
+
+        Ok(())
+    }
+
+    /// Append atoms, bonds, and other connectivity of another topology to this topology.
+    pub(super) fn append(&mut self, other: TprTopology) {
+        self.atoms.extend(other.atoms);
+        self.bonds.extend(other.bonds);
+        self.urey_bradley_pairs.extend(other.urey_bradley_pairs);
+        self.cmap_terms.extend(other.cmap_terms);
+        self.restraints.extend(other.restraints);
     }
 
     /// Get positions, velocities, and forces for particles in the topology from the `Coordinates` structure.
-    pub(super) fn fill_with_coordinates(&mut self, coordinates: Coordinates) {
-        for (pos, atom) in coordinates.positions.into_iter().zip(self.atoms.iter_mut()) {
-            atom.position = Some(pos);
+    pub(super) fn fill_with_coordinates(&mut self, coordinates: &Coordinates) {
+        for (pos, atom) in coordinates.positions.iter().zip(self.atoms.iter_mut()) {
+            atom.position = Some(*pos);
         }
 
-        for (vel, atom) in coordinates
-            .velocities
-            .into_iter()
-            .zip(self.atoms.iter_mut())
-        {
-            atom.velocity = Some(vel);
+        for (vel, atom) in coordinates.velocities.iter().zip(self.atoms.iter_mut()) {
+            atom.velocity = Some(*vel);
         }
 
-        for (force, atom) in coordinates.forces.into_iter().zip(self.atoms.iter_mut()) {
-            atom.force = Some(force);
+        for (force, atom) in coordinates.forces.iter().zip(self.atoms.iter_mut()) {
+            atom.force = Some(*force);
+        }
+    }
+}
+
+/// Identifiers assigned to the chains, in order.
+const CHAIN_IDS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+/// Assign chain identifiers to all atoms.
+/// Each molecule with more than one residue forms a new chain.
+/// Atoms of single-residue molecules get a blank chain identifier.
+fn assign_chains(atoms: &mut [Atom]) {
+    let mut chain_index = 0;
+    for molecule in atoms.chunk_by_mut(|a, b| a.molecule_id == b.molecule_id) {
+        let first_residue = molecule[0].residue_number;
+        let chain_id = if molecule
+            .iter()
+            .any(|atom| atom.residue_number != first_residue)
+        {
+            let id = CHAIN_IDS[chain_index % CHAIN_IDS.len()] as char;
+            chain_index += 1;
+            id
+        } else {
+            ' '
+        };
+
+        for atom in molecule.iter_mut() {
+            atom.chain_id = chain_id;
         }
     }
 }
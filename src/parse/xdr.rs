@@ -5,24 +5,139 @@
 
 use std::{
     fs::File,
-    io::{BufReader, Error, Read},
+    io::{BufReader, Cursor, Error, Read, Seek, SeekFrom},
 };
 
 use byteorder::{BigEndian, ReadBytesExt};
 
-use crate::structures::Precision;
+use crate::{errors::ParseTprError, structures::Precision};
+
+/// Source of the data of the TPR file being read.
+#[derive(Debug)]
+enum XdrSource<'a> {
+    /// Buffered file on disk.
+    File(BufReader<File>),
+    /// Bytes in memory.
+    Bytes(Cursor<&'a [u8]>),
+}
+
+impl Read for XdrSource<'_> {
+    #[inline(always)]
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        match self {
+            XdrSource::File(reader) => reader.read(buf),
+            XdrSource::Bytes(cursor) => cursor.read(buf),
+        }
+    }
+
+    #[inline(always)]
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Error> {
+        match self {
+            XdrSource::File(reader) => reader.read_exact(buf),
+            XdrSource::Bytes(cursor) => cursor.read_exact(buf),
+        }
+    }
+}
+
+impl Seek for XdrSource<'_> {
+    #[inline(always)]
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64, Error> {
+        match self {
+            XdrSource::File(reader) => reader.seek(pos),
+            XdrSource::Bytes(cursor) => cursor.seek(pos),
+        }
+    }
+
+    #[inline(always)]
+    fn stream_position(&mut self) -> Result<u64, Error> {
+        match self {
+            XdrSource::File(reader) => reader.stream_position(),
+            XdrSource::Bytes(cursor) => cursor.stream_position(),
+        }
+    }
+
+    /// Relative seek which does not discard the buffer of the file reader.
+    #[inline(always)]
+    fn seek_relative(&mut self, offset: i64) -> Result<(), Error> {
+        match self {
+            XdrSource::File(reader) => reader.seek_relative(offset),
+            XdrSource::Bytes(cursor) => cursor.seek_relative(offset),
+        }
+    }
+}
 
 /// Structure representing the TPR file being read.
 #[derive(Debug)]
-pub(super) struct XdrFile {
-    reader: BufReader<File>,
+pub(super) struct XdrFile<'a> {
+    reader: XdrSource<'a>,
+    /// Size of the file in bytes.
+    file_size: u64,
 }
 
-impl XdrFile {
-    /// Create a new `XdrFile` structure.
+impl<'a> XdrFile<'a> {
+    /// Create a new `XdrFile` structure reading from a file.
     #[inline(always)]
     pub(super) fn new(reader: BufReader<File>) -> Self {
-        XdrFile { reader }
+        let file_size = reader
+            .get_ref()
+            .metadata()
+            .map(|metadata| metadata.len())
+            .unwrap_or(u64::MAX);
+
+        XdrFile {
+            reader: XdrSource::File(reader),
+            file_size,
+        }
+    }
+
+    /// Create a new `XdrFile` structure reading from bytes in memory.
+    #[inline(always)]
+    pub(super) fn from_bytes(bytes: &'a [u8]) -> Self {
+        XdrFile {
+            reader: XdrSource::Bytes(Cursor::new(bytes)),
+            file_size: bytes.len() as u64,
+        }
+    }
+
+    /// Get the current position in the file (in bytes from the start of the file).
+    #[inline(always)]
+    pub(super) fn position(&mut self) -> Result<u64, Error> {
+        self.reader.stream_position()
+    }
+
+    /// Get the number of bytes remaining in the file.
+    #[inline(always)]
+    pub(super) fn remaining(&mut self) -> Result<u64, Error> {
+        Ok(self.file_size.saturating_sub(self.position()?))
+    }
+
+    /// Check that `n_items` items, each occupying at least `item_size` bytes,
+    /// can still be read from the file.
+    /// Returns `ParseTprError::ImplausibleLength` if this is not possible.
+    #[inline(always)]
+    fn check_length(&mut self, n_items: u64, item_size: u64) -> Result<(), ParseTprError> {
+        if n_items.saturating_mul(item_size) > self.remaining()? {
+            Err(ParseTprError::ImplausibleLength(
+                i64::try_from(n_items).unwrap_or(i64::MAX),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Read the number of items that follow in the file.
+    /// Each item is expected to occupy at least `item_size` bytes.
+    ///
+    /// Returns `ParseTprError::ImplausibleLength` if the number is negative
+    /// or if the items can not fit into the rest of the file.
+    pub(super) fn read_count(&mut self, item_size: u64) -> Result<usize, ParseTprError> {
+        let count = self.read_i32()?;
+        if count < 0 {
+            return Err(ParseTprError::ImplausibleLength(count as i64));
+        }
+
+        self.check_length(count as u64, item_size)?;
+        Ok(count as usize)
     }
 
     /// Jump forward by N bytes.
@@ -115,8 +230,8 @@ impl XdrFile {
         n_reals: i64,
     ) -> Result<(), Error> {
         match precision {
-            Precision::Single => self.jump(4 * n_reals),
-            Precision::Double => self.jump(8 * n_reals),
+            Precision::Single => self.jump(n_reals.saturating_mul(4)),
+            Precision::Double => self.jump(n_reals.saturating_mul(8)),
         }
     }
 
@@ -128,7 +243,7 @@ impl XdrFile {
 
     /// Read a string with one useless 4byte header and one useful 4byte header from `XdrFile`.
     /// This is used for a) the tpr file header and b) for the body of tpr files version < 119.
-    pub(super) fn read_string_4byte(&mut self) -> Result<String, Error> {
+    pub(super) fn read_string_4byte(&mut self) -> Result<String, ParseTprError> {
         // first 4 bytes of the string header are not used
         self.reader.seek_relative(4)?;
 
@@ -137,9 +252,11 @@ impl XdrFile {
 
         // make sure that the length is a multiple of 4
         if len % 4 != 0 {
-            len += 4 - (len % 4);
+            len = len.saturating_add(4 - (len % 4));
         }
 
+        self.check_length(len as u64, 1)?;
+
         // read string
         let mut bytes: Vec<u8> = vec![0; len as usize];
         self.reader.read_exact(&mut bytes)?;
@@ -150,9 +267,10 @@ impl XdrFile {
 
     /// Read a string with one useful 8byte header from `XdrFile`.
     /// This is used for the body of the tpr files version >= 119.
-    pub(super) fn read_string_8byte(&mut self) -> Result<String, Error> {
+    pub(super) fn read_string_8byte(&mut self) -> Result<String, ParseTprError> {
         // get length of the string
         let len = self.read_u64()?;
+        self.check_length(len, 1)?;
 
         // read string
         let mut bytes: Vec<u8> = vec![0; len as usize];
@@ -166,7 +284,7 @@ impl XdrFile {
     /// This calls either `read_string_4byte` or `read_string_8byte` depending on the
     /// version of the tpr file.
     #[inline(always)]
-    pub(super) fn read_string_body(&mut self, tpr_version: i32) -> Result<String, Error> {
+    pub(super) fn read_string_body(&mut self, tpr_version: i32) -> Result<String, ParseTprError> {
         if tpr_version < 119 {
             self.read_string_4byte()
         } else {
@@ -202,32 +320,13 @@ impl XdrFile {
     pub(super) fn read_bool_body(&mut self, tpr_version: i32) -> Result<bool, Error> {
         Ok(self.read_uchar_body(tpr_version)? != 0)
     }
-
-    /// Skip multiple unsigned char (or boolean) values from the body of the tpr file.
-    /// This skips either `4n` or `n` bytes, depending on the version of the tpr file.
-    #[inline(always)]
-    pub(super) fn skip_multiple_uchars_body(
-        &mut self,
-        tpr_version: i32,
-        n_uchars: i64,
-    ) -> Result<(), Error> {
-        if tpr_version < 119 {
-            self.jump(4 * n_uchars)
-        } else {
-            self.jump(n_uchars)
-        }
-    }
 }
 
 /// Convert bytes to Rust string.
-///
-/// ## Panic
-/// Panics if the conversion is not possible.
+/// Invalid UTF-8 sequences are replaced with `U+FFFD REPLACEMENT CHARACTER`.
 #[inline(always)]
 fn bytes2string(bytes: &[u8]) -> String {
     let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
 
-    std::str::from_utf8(&bytes[..end])
-        .expect("FATAL MINITPR ERROR | bytes2string | Could not construct String from bytes.")
-        .to_owned()
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
 }
@@ -0,0 +1,264 @@
+// Released under Apache License 2.0 / MIT License.
+// Copyright (c) 2024 Ladislav Bartos
+
+//! This file contains the implementation of the `TprParser` builder.
+
+use std::{
+    io::Read,
+    path::Path,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{
+    errors::{ParseTprError, ParseWarning, TprResult},
+    parse,
+    structures::{ParseOptions, ParseStage, TprFile, TprParser},
+};
+
+impl TprParser {
+    /// Create a new `TprParser` with the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read positions, velocities, and forces of the atoms. Defaults to `true`.
+    pub fn coordinates(mut self, read: bool) -> Self {
+        self.options.skip_coordinates = !read;
+        self
+    }
+
+    /// Keep B-state masses and charges of the atoms. Defaults to `true`.
+    pub fn read_bstate(mut self, read: bool) -> Self {
+        self.options.skip_bstate = !read;
+        self
+    }
+
+    /// Remove duplicate bonds from the topology. Defaults to `false`.
+    /// See [`ParseOptions::deduplicate_bonds`].
+    pub fn deduplicate_bonds(mut self, deduplicate: bool) -> Self {
+        self.options.deduplicate_bonds = deduplicate;
+        self
+    }
+
+    /// Sort the bonds of the topology. Defaults to `false`.
+    /// See [`ParseOptions::sort_bonds`].
+    pub fn sort_bonds(mut self, sort: bool) -> Self {
+        self.options.sort_bonds = sort;
+        self
+    }
+
+    /// Guess missing elements of atoms from their names. Defaults to `false`.
+    /// See [`ParseOptions::fill_missing_elements`].
+    pub fn fill_missing_elements(mut self, fill: bool) -> Self {
+        self.options.fill_missing_elements = fill;
+        self
+    }
+
+    /// Remove leading and trailing whitespace from names. Defaults to `false`.
+    /// See [`ParseOptions::trim_names`].
+    pub fn trim_names(mut self, trim: bool) -> Self {
+        self.options.trim_names = trim;
+        self
+    }
+
+    /// Add the H-H bond of each SETTLE interaction. Defaults to `false`.
+    /// See [`ParseOptions::settle_include_hh`].
+    pub fn settle_include_hh(mut self, include: bool) -> Self {
+        self.options.settle_include_hh = include;
+        self
+    }
+
+    /// Keep the contiguous blocks of coordinates. Defaults to `false`.
+    /// See [`ParseOptions::keep_coordinate_blocks`].
+    pub fn keep_coordinate_blocks(mut self, keep: bool) -> Self {
+        self.options.keep_coordinate_blocks = keep;
+        self
+    }
+
+    /// Check the integrity of the parsed topology. Defaults to `false`.
+    /// See [`ParseOptions::strict`].
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.options.strict = strict;
+        self
+    }
+
+    /// Keep the symbol table of the tpr file. Defaults to `false`.
+    /// See [`ParseOptions::keep_symbol_table`].
+    pub fn keep_symbol_table(mut self, keep: bool) -> Self {
+        self.options.keep_symbol_table = keep;
+        self
+    }
+
+    /// Attempt to parse tpr files with untested versions. Defaults to `false`.
+    /// See [`ParseOptions::allow_untested_version`].
+    pub fn allow_untested_version(mut self, allow: bool) -> Self {
+        self.options.allow_untested_version = allow;
+        self
+    }
+
+    /// Attempt to parse tpr files written by Gromacs 5.0. Defaults to `false`.
+    /// See [`ParseOptions::allow_legacy`].
+    pub fn allow_legacy(mut self, allow: bool) -> Self {
+        self.options.allow_legacy = allow;
+        self
+    }
+
+    /// Parse a Gromacs tpr file using the options of the parser.
+    ///
+    /// ## Parameters
+    /// - `filename`: path to the tpr file to read
+    ///
+    /// ## Returns
+    /// - [`TprFile`](`crate::TprFile`) structure, if successful.
+    /// - Otherwise [`ParseTprError`](`crate::errors::ParseTprError`).
+    ///
+    /// ## Notes
+    /// - See [`TprFile::parse`] for more information.
+    pub fn parse(&self, filename: impl AsRef<Path>) -> Result<TprFile, ParseTprError> {
+        parse::parse_tpr(filename, &self.options, &mut |_| (), &mut Vec::new())
+    }
+
+    /// Parse a Gromacs tpr file stored in memory using the options of the parser.
+    ///
+    /// ## Parameters
+    /// - `bytes`: content of the tpr file
+    ///
+    /// ## Returns
+    /// - [`TprFile`](`crate::TprFile`) structure, if successful.
+    /// - Otherwise [`ParseTprError`](`crate::errors::ParseTprError`).
+    ///
+    /// ## Notes
+    /// - See [`TprFile::parse_bytes`] for more information.
+    pub fn parse_bytes(&self, bytes: &[u8]) -> Result<TprFile, ParseTprError> {
+        parse::parse_tpr_bytes(bytes, &self.options, &mut |_| (), &mut Vec::new())
+    }
+
+    /// Parse a Gromacs tpr file read from the standard input using the options of the parser.
+    ///
+    /// ## Returns
+    /// - [`TprFile`](`crate::TprFile`) structure, if successful.
+    /// - Otherwise [`ParseTprError`](`crate::errors::ParseTprError`).
+    ///
+    /// ## Notes
+    /// - See [`TprFile::parse_stdin`] for more information.
+    pub fn parse_stdin(&self) -> Result<TprFile, ParseTprError> {
+        let mut bytes = Vec::new();
+        std::io::stdin().lock().read_to_end(&mut bytes)?;
+        self.parse_bytes(&bytes)
+    }
+
+    /// Parse a Gromacs tpr file using the options of the parser, reporting the progress of the parsing.
+    ///
+    /// ## Parameters
+    /// - `filename`: path to the tpr file to read
+    /// - `progress`: function called whenever a milestone of the parsing is reached
+    ///   (see [`ParseStage`](`crate::ParseStage`))
+    ///
+    /// ## Returns
+    /// - [`TprFile`](`crate::TprFile`) structure, if successful.
+    /// - Otherwise [`ParseTprError`](`crate::errors::ParseTprError`).
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use minitpr::{ParseStage, TprParser};
+    ///
+    /// let tpr = TprParser::new()
+    ///     .parse_with_progress("topol.tpr", |stage| {
+    ///         if let ParseStage::TopologyDone(n_atoms) = stage {
+    ///             println!("Constructed {} atoms.", n_atoms);
+    ///         }
+    ///     })
+    ///     .expect("Could not parse tpr file.");
+    /// ```
+    pub fn parse_with_progress(
+        &self,
+        filename: impl AsRef<Path>,
+        mut progress: impl FnMut(ParseStage),
+    ) -> Result<TprFile, ParseTprError> {
+        parse::parse_tpr(filename, &self.options, &mut progress, &mut Vec::new())
+    }
+
+    /// Parse a Gromacs tpr file using the options of the parser, collecting warnings about the anomalies
+    /// encountered during the parsing.
+    ///
+    /// ## Parameters
+    /// - `filename`: path to the tpr file to read
+    ///
+    /// ## Returns
+    /// - [`TprFile`](`crate::TprFile`) structure and a vector of [`ParseWarning`](`crate::errors::ParseWarning`), if successful.
+    /// - Otherwise [`ParseTprError`](`crate::errors::ParseTprError`).
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use minitpr::TprParser;
+    ///
+    /// let (tpr, warnings) = TprParser::new()
+    ///     .parse_with_warnings("topol.tpr")
+    ///     .expect("Could not parse tpr file.");
+    ///
+    /// for warning in warnings {
+    ///     eprintln!("{}", warning);
+    /// }
+    /// ```
+    pub fn parse_with_warnings(
+        &self,
+        filename: impl AsRef<Path>,
+    ) -> Result<(TprFile, Vec<ParseWarning>), ParseTprError> {
+        let mut warnings = Vec::new();
+        let tpr = parse::parse_tpr(filename, &self.options, &mut |_| (), &mut warnings)?;
+        Ok((tpr, warnings))
+    }
+
+    /// Parse multiple Gromacs tpr files in parallel using the options of the parser.
+    ///
+    /// ## Parameters
+    /// - `paths`: paths to the tpr files to read
+    ///
+    /// ## Returns
+    /// Vector containing the result of parsing for each of the files, in the order of `paths`.
+    ///
+    /// ## Notes
+    /// - The files are distributed among at most as many threads as there are available CPUs
+    ///   (see [`std::thread::available_parallelism`]).
+    /// - Errors are reported for each file separately. A file that fails to parse
+    ///   does not stop the parsing of the other files.
+    pub fn parse_many<P: AsRef<Path> + Sync>(&self, paths: &[P]) -> Vec<TprResult<TprFile>> {
+        let n_threads = std::thread::available_parallelism()
+            .map_or(1, |n| n.get())
+            .min(paths.len());
+
+        // index of the next file to parse
+        let next = AtomicUsize::new(0);
+
+        let mut results = std::thread::scope(|scope| {
+            let handles = (0..n_threads)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut parsed = Vec::new();
+                        loop {
+                            let index = next.fetch_add(1, Ordering::Relaxed);
+                            match paths.get(index) {
+                                Some(path) => parsed.push((index, self.parse(path))),
+                                None => return parsed,
+                            }
+                        }
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().expect("Thread parsing tpr files panicked."))
+                .collect::<Vec<_>>()
+        });
+
+        results.sort_unstable_by_key(|(index, _)| *index);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+}
+
+impl From<ParseOptions> for TprParser {
+    fn from(options: ParseOptions) -> Self {
+        TprParser { options }
+    }
+}
@@ -0,0 +1,234 @@
+// Released under Apache License 2.0 / MIT License.
+// Copyright (c) 2024 Ladislav Bartos
+
+//! This file contains methods for working with the simulation box.
+
+use crate::{
+    structures::{BoxShape, SimBox},
+    DIM,
+};
+
+/// Relative tolerance used when comparing lengths of box vectors in `SimBox::shape`.
+const LENGTH_TOLERANCE: f64 = 1e-4;
+/// Absolute tolerance (in degrees) used when comparing box angles in `SimBox::shape`.
+const ANGLE_TOLERANCE: f64 = 0.01;
+
+impl SimBox {
+    /// Calculate the volume of the simulation box.
+    ///
+    /// ## Notes
+    /// - The volume is calculated as the determinant of the `simbox` matrix,
+    ///   so it is valid for any triclinic box.
+    pub fn volume(&self) -> f64 {
+        let m = &self.simbox;
+
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    /// Get the lengths of the simulation box vectors.
+    ///
+    /// ## Returns
+    /// Lengths of the box vectors `a`, `b`, and `c` (rows of the `simbox` matrix) in nm.
+    pub fn lengths(&self) -> [f64; 3] {
+        self.simbox.map(|vector| norm(&vector))
+    }
+
+    /// Get the angles between the simulation box vectors.
+    ///
+    /// ## Returns
+    /// Angles `α` (between vectors `b` and `c`), `β` (between `a` and `c`),
+    /// and `γ` (between `a` and `b`) in degrees.
+    /// Angles involving a zero-length vector are reported as 90°.
+    pub fn angles(&self) -> [f64; 3] {
+        let [a, b, c] = &self.simbox;
+        [angle(b, c), angle(a, c), angle(a, b)]
+    }
+
+    /// Check whether the simulation box is orthogonal.
+    ///
+    /// ## Parameters
+    /// - `tol`: maximal absolute value of an off-diagonal element of the `simbox` matrix
+    ///   for it to be considered zero
+    pub fn is_orthogonal(&self, tol: f64) -> bool {
+        (0..DIM).all(|i| (0..DIM).all(|j| i == j || self.simbox[i][j].abs() <= tol))
+    }
+
+    /// Classify the shape of the simulation box.
+    ///
+    /// ## Notes
+    /// - Rhombic dodecahedra and truncated octahedra are detected heuristically from
+    ///   the lengths of the box vectors and the angles between them, assuming the box
+    ///   vectors were constructed the way Gromacs constructs them.
+    /// - Boxes matching none of the specific shapes are classified as `BoxShape::Triclinic`.
+    pub fn shape(&self) -> BoxShape {
+        let [a, b, c] = self.lengths();
+        let same_lengths = (a - b).abs() <= LENGTH_TOLERANCE * a.max(b)
+            && (a - c).abs() <= LENGTH_TOLERANCE * a.max(c);
+
+        let angles_match = |expected: [f64; 3]| {
+            self.angles()
+                .iter()
+                .zip(expected)
+                .all(|(angle, exp)| (angle - exp).abs() <= ANGLE_TOLERANCE)
+        };
+
+        if self
+            .angles()
+            .iter()
+            .all(|&angle| (angle - 90.0).abs() <= ANGLE_TOLERANCE)
+        {
+            if same_lengths {
+                BoxShape::Cubic
+            } else {
+                BoxShape::Rectangular
+            }
+        } else if same_lengths && (angles_match([60.0, 60.0, 90.0]) || angles_match([60.0; 3])) {
+            BoxShape::Dodecahedral
+        } else if same_lengths && angles_match([70.528779, 109.471221, 70.528779]) {
+            BoxShape::Octahedral
+        } else {
+            BoxShape::Triclinic
+        }
+    }
+
+    /// Calculate the minimum-image displacement vector between two points.
+    ///
+    /// ## Parameters
+    /// - `a`: position of the first point
+    /// - `b`: position of the second point
+    ///
+    /// ## Returns
+    /// The shortest vector pointing from `a` to any periodic image of `b`.
+    ///
+    /// ## Notes
+    /// - Works for both orthogonal and triclinic boxes. The displacement is first reduced
+    ///   using the box vectors (starting from the last one) and then compared with
+    ///   the displacements to the neighboring periodic images.
+    /// - Dimensions with a non-positive diagonal element are not treated as periodic.
+    pub fn min_image(&self, a: [f64; DIM], b: [f64; DIM]) -> [f64; DIM] {
+        let mut dx = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+
+        for m in (0..DIM).rev() {
+            let length = self.simbox[m][m];
+            if length <= 0.0 {
+                continue;
+            }
+
+            let shift = (dx[m] / length).round();
+            for (coord, vector) in dx.iter_mut().zip(self.simbox[m]) {
+                *coord -= shift * vector;
+            }
+        }
+
+        // for strongly skewed boxes, a neighboring image can still be closer
+        let periodic = |m: usize| {
+            if self.simbox[m][m] > 0.0 {
+                -1..=1
+            } else {
+                0..=0
+            }
+        };
+        let mut best = dx;
+        for i in periodic(0) {
+            for j in periodic(1) {
+                for k in periodic(2) {
+                    let shift = [i as f64, j as f64, k as f64];
+                    let mut candidate = dx;
+                    for (d, coord) in candidate.iter_mut().enumerate() {
+                        *coord += (0..DIM).map(|m| shift[m] * self.simbox[m][d]).sum::<f64>();
+                    }
+
+                    if dot(&candidate, &candidate) < dot(&best, &best) {
+                        best = candidate;
+                    }
+                }
+            }
+        }
+
+        best
+    }
+
+    /// Calculate the reciprocal vectors of the simulation box.
+    ///
+    /// ## Returns
+    /// - Vectors `a*`, `b*`, and `c*` such that the fractional coordinates of a position
+    ///   are its dot products with these vectors. The distance between the opposite faces
+    ///   of the box is the inverse length of the corresponding reciprocal vector.
+    /// - `None` if the volume of the box is zero.
+    pub(crate) fn reciprocal(&self) -> Option<[[f64; DIM]; DIM]> {
+        let volume = self.volume();
+        if volume == 0.0 {
+            return None;
+        }
+
+        let [a, b, c] = &self.simbox;
+        Some([cross(b, c), cross(c, a), cross(a, b)].map(|v| v.map(|x| x / volume)))
+    }
+
+    /// Wrap a position into the primary unit cell of the simulation box.
+    ///
+    /// ## Notes
+    /// - Follows the Gromacs convention: box vectors are subtracted (or added)
+    ///   starting from the last one, so that each coordinate of the wrapped position
+    ///   lies within `[0, simbox[m][m])`.
+    /// - Dimensions with a non-positive diagonal element are not wrapped.
+    pub(crate) fn wrap(&self, mut position: [f64; DIM]) -> [f64; DIM] {
+        for m in (0..DIM).rev() {
+            let length = self.simbox[m][m];
+            if length <= 0.0 {
+                continue;
+            }
+
+            let shift = (position[m] / length).floor();
+            for (coord, vector) in position.iter_mut().zip(self.simbox[m]) {
+                *coord -= shift * vector;
+            }
+
+            // guard against rounding errors placing the atom exactly at the box edge
+            if position[m] >= length {
+                for (coord, vector) in position.iter_mut().zip(self.simbox[m]) {
+                    *coord -= vector;
+                }
+            }
+        }
+
+        position
+    }
+}
+
+/// Calculate the length of a vector.
+#[inline(always)]
+fn norm(vector: &[f64; DIM]) -> f64 {
+    dot(vector, vector).sqrt()
+}
+
+/// Calculate the dot product of two vectors.
+#[inline(always)]
+fn dot(vector1: &[f64; DIM], vector2: &[f64; DIM]) -> f64 {
+    vector1.iter().zip(vector2).map(|(x, y)| x * y).sum()
+}
+
+/// Calculate the cross product of two vectors.
+#[inline(always)]
+fn cross(vector1: &[f64; DIM], vector2: &[f64; DIM]) -> [f64; DIM] {
+    [
+        vector1[1] * vector2[2] - vector1[2] * vector2[1],
+        vector1[2] * vector2[0] - vector1[0] * vector2[2],
+        vector1[0] * vector2[1] - vector1[1] * vector2[0],
+    ]
+}
+
+/// Calculate the angle between two vectors in degrees.
+fn angle(vector1: &[f64; DIM], vector2: &[f64; DIM]) -> f64 {
+    let norms = norm(vector1) * norm(vector2);
+    if norms == 0.0 {
+        return 90.0;
+    }
+
+    (dot(vector1, vector2) / norms)
+        .clamp(-1.0, 1.0)
+        .acos()
+        .to_degrees()
+}
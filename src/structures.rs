@@ -5,6 +5,8 @@
 
 pub use mendeleev::Element;
 
+use std::ops::Range;
+
 use crate::DIM;
 
 /// Structure representing the TPR file.
@@ -14,15 +16,164 @@ pub struct TprFile {
     /// TPR file header.
     pub header: TprHeader,
     /// Name of the molecular system.
+    /// Trailing whitespace and control characters are removed during parsing.
+    /// See also [`TprFile::system_name`](crate::TprFile::system_name).
     pub system_name: String,
     /// Dimensions of the simulation box.
     pub simbox: Option<SimBox>,
     /// System topology.
     pub topology: TprTopology,
+    /// Molecule blocks of the system in the order in which they appear in the tpr file.
+    pub molecule_blocks: Vec<MolBlockView>,
+    /// Atom groups used by the simulation (temperature-coupling groups, energy groups, etc.).
+    pub groups: SimulationGroups,
+    /// All strings stored in the symbol table of the tpr file (names of atoms, residues, atom types, groups, etc.).
+    /// Only present if the file has been parsed with [`ParseOptions::keep_symbol_table`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub symbol_table: Option<Vec<String>>,
+    /// Molecule types of the system in the order in which they appear in the tpr file.
+    /// Molecule blocks refer to the molecule types using `MolBlockView::molecule_type`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub molecule_types: Vec<MoleculeTypeView>,
+    /// Interactions between atoms of different molecules (`intermolecular_interactions` section of the topology).
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub intermolecular_interactions: Vec<Interaction>,
+    /// Contiguous blocks of positions, velocities, and forces of all atoms.
+    /// Only present if the file has been parsed with [`ParseOptions::keep_coordinate_blocks`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub coordinates: Option<Coordinates>,
+}
+
+/// Options controlling the parsing of the tpr file.
+///
+/// ## Example
+/// ```no_run
+/// use minitpr::{ParseOptions, TprFile};
+///
+/// let options = ParseOptions {
+///     deduplicate_bonds: true,
+///     ..Default::default()
+/// };
+///
+/// let tpr = TprFile::parse_with_options("topol.tpr", &options).expect("Could not parse tpr file.");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// Remove duplicate bonds from the topology. Defaults to `false`.
+    ///
+    /// The same pair of atoms can be bonded by several interactions, e.g., by a bond
+    /// (`F_BONDS`) and by a constraint (`F_CONSTR`). Such pairs are by default listed
+    /// multiple times in `TprTopology::bonds`. Bonds obtained from SETTLE interactions
+    /// are deduplicated as well, if the same atoms are also connected by a bond or a constraint.
+    /// Bonds are compared after normalization, i.e., `0-1` and `1-0` are considered identical.
+    /// The first occurrence of each bond is kept.
+    pub deduplicate_bonds: bool,
+    /// Sort the bonds of the topology. Defaults to `false`.
+    ///
+    /// Bonds are normalized and sorted by the indices of the bonded atoms,
+    /// see [`TprTopology::sort_bonds`]. Sorting is performed after deduplication.
+    pub sort_bonds: bool,
+    /// Attempt to parse tpr files with version higher than [`MAX_TESTED_VERSION`](`crate::MAX_TESTED_VERSION`).
+    /// Defaults to `false`.
+    ///
+    /// The format of such files may have changed in a way unknown to `minitpr`.
+    /// The parsing may then fail or, in the worst case, silently produce invalid data.
+    pub allow_untested_version: bool,
+    /// Attempt to parse tpr files with version 100–102 (Gromacs 5.0). Defaults to `false`.
+    ///
+    /// `minitpr` assumes that these files differ from version 103 only in the absence of intermolecular interactions.
+    /// This was only checked using a Gromacs 5.1 tpr file manually converted to version 102;
+    /// parsing of files actually written by Gromacs 5.0 is unverified.
+    /// Tpr files older than version 100 are always rejected.
+    pub allow_legacy: bool,
+    /// Keep the symbol table of the tpr file in `TprFile::symbol_table`. Defaults to `false`.
+    pub keep_symbol_table: bool,
+    /// Do not read positions, velocities, and forces of the atoms. Defaults to `false`.
+    ///
+    /// The coordinates of all atoms are then `None`, even if they are present in the tpr file.
+    pub skip_coordinates: bool,
+    /// Keep the contiguous blocks of positions, velocities, and forces in `TprFile::coordinates`.
+    /// Defaults to `false`.
+    ///
+    /// The coordinates are still assigned to the individual atoms.
+    /// Has no effect if `skip_coordinates` is `true`.
+    pub keep_coordinate_blocks: bool,
+    /// Do not keep B-state masses and charges of the atoms. Defaults to `false`.
+    ///
+    /// `Atom::mass_b` and `Atom::charge_b` are then `None` for all atoms.
+    pub skip_bstate: bool,
+    /// Guess elements of atoms which have no element assigned in the tpr file
+    /// from their names (see [`TprTopology::fill_missing_elements`](crate::TprTopology::fill_missing_elements)).
+    /// Defaults to `false`.
+    pub fill_missing_elements: bool,
+    /// Remove leading and trailing ASCII whitespace from the names of atoms, residues,
+    /// and molecule types. Defaults to `false`.
+    ///
+    /// Some force fields pad the names with spaces. Gromacs itself uses the names
+    /// exactly as they are stored, so trimming is opt-in.
+    pub trim_names: bool,
+    /// Also add a bond between the two hydrogen atoms of each SETTLE interaction. Defaults to `false`.
+    ///
+    /// SETTLE keeps both O-H distances and the H-H distance of a water molecule fixed.
+    /// By default, only the two O-H bonds are added to the topology.
+    pub settle_include_hh: bool,
+    /// Check the integrity of the parsed topology (see [`TprTopology::validate`]). Defaults to `false`.
+    ///
+    /// If the topology is inconsistent, the parsing fails with the corresponding error.
+    pub strict: bool,
+}
+
+/// Builder for parsing tpr files with non-default options.
+///
+/// ## Example
+/// ```no_run
+/// use minitpr::TprParser;
+///
+/// let tpr = TprParser::new()
+///     .coordinates(false)
+///     .deduplicate_bonds(true)
+///     .parse("topol.tpr")
+///     .expect("Could not parse tpr file.");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TprParser {
+    /// Options used for the parsing.
+    pub options: ParseOptions,
+}
+
+/// Enum representing the milestones of tpr file parsing.
+/// Reported by [`TprParser::parse_with_progress`](crate::TprParser::parse_with_progress).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseStage {
+    /// Header of the tpr file has been read.
+    HeaderDone,
+    /// Symbol table has been read.
+    SymtabDone,
+    /// Force-field parameters have been read.
+    FFParamsDone,
+    /// A molecule block has been added to the topology.
+    /// Contains the number of atoms in the topology so far.
+    /// Reported once for each molecule block.
+    TopologyDone(usize),
+    /// Positions, velocities, and forces have been read.
+    /// Not reported if coordinates are not read (see [`ParseOptions::skip_coordinates`]).
+    CoordinatesDone,
 }
 
 /// Structure representing the header of the TPR file.
-#[derive(Debug, Clone)]
+///
+/// With the `serde` feature enabled, the header can be (de)serialized independently
+/// of the rest of the `TprFile`.
+#[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TprHeader {
     /// Gromacs version used to write the tpr file.
@@ -39,9 +190,15 @@ pub struct TprHeader {
     pub n_atoms: i32,
     /// Number of temperature coupling groups.
     pub n_coupling_groups: i32,
-    /// Value of the alchemical state.
+    /// Index of the initial alchemical (lambda) state, i.e., `init-lambda-state` from the mdp file.
+    /// Negative if the state is not specified. See [`TprHeader::lambda_state`].
     pub fep_state: i32,
-    /// Value of lambda.
+    /// Initial value of the `fep` component of the lambda vector.
+    ///
+    /// This is a single scalar, not the full lambda vector. The individual components
+    /// (`coul`, `vdw`, `bonded`, `restraint`, `mass`, `temperature`) are stored
+    /// in the input record, which is not parsed by `minitpr`.
+    /// If the simulation uses `init-lambda`, all components share this value.
     pub lambda: f64,
     /// Is input record present?
     pub has_input_record: bool,
@@ -66,8 +223,370 @@ pub struct TprTopology {
     /// List of atoms in the system.
     pub atoms: Vec<Atom>,
     /// List of bonds between atoms in the system.
-    /// The order of bonds is undefined.
+    /// The order of bonds is undefined, unless the bonds are sorted
+    /// using [`TprTopology::sort_bonds`].
     pub bonds: Vec<Bond>,
+    /// List of 1-3 pairs implied by the Urey-Bradley interactions (`F_UREY_BRADLEY`),
+    /// i.e., pairs of the outer atoms of each Urey-Bradley angle.
+    /// These pairs are not part of `bonds`.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub urey_bradley_pairs: Vec<Bond>,
+    /// List of atom quintuples coupled by the CMAP correction terms (`F_CMAP`).
+    /// Each quintuple contains global atom indices defining the two consecutive
+    /// backbone dihedrals (φ and ψ) the correction term applies to.
+    /// The correction grids are not read.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub cmap_terms: Vec<[usize; 5]>,
+    /// List of distance, orientation, and dihedral restraints applied to the system.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub restraints: Vec<Restraint>,
+}
+
+/// Structure holding positions, velocities, and forces of all atoms of the system
+/// as contiguous blocks, in the order of atoms in the topology.
+/// If a vector is empty, the corresponding property is not present in the tpr file.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Coordinates {
+    /// Positions of atoms in the system.
+    pub positions: Vec<[f64; 3]>,
+    /// Velocities of atoms in the system.
+    pub velocities: Vec<[f64; 3]>,
+    /// Forces acting upon the atoms of the system.
+    pub forces: Vec<[f64; 3]>,
+}
+
+/// Structure representing an interaction between atoms, as stored in the tpr file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Interaction {
+    /// Type of the interaction.
+    pub interaction_type: InteractionType,
+    /// Indices of the interacting atoms.
+    /// For interactions of a molecule type, these are indices of the atoms within the molecule type
+    /// (starting from 0). For intermolecular interactions, these are global atom indices.
+    pub interacting_atom_indices: AtomIndices,
+}
+
+/// Indices of the atoms involved in an interaction.
+/// The indices are stored inline (without heap allocation) and can be accessed as a slice.
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "Vec<usize>", into = "Vec<usize>")
+)]
+pub struct AtomIndices {
+    /// Number of atoms involved in the interaction.
+    pub(crate) len: u8,
+    /// Indices of the atoms. Only the first `len` items are valid, the rest are zero.
+    pub(crate) indices: [usize; crate::MAX_INTERACTING_ATOMS],
+}
+
+/// Structure representing a molecule type of the system.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MoleculeTypeView {
+    /// Name of the molecule type.
+    pub name: String,
+    /// Atoms of the molecule type.
+    pub atoms: Vec<MoleculeTypeAtomView>,
+    /// Residues of the molecule type.
+    pub residues: Vec<MoleculeTypeResidueView>,
+    /// Interactions between the atoms of the molecule type.
+    pub interactions: Vec<Interaction>,
+}
+
+/// Structure representing an atom of a molecule type.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MoleculeTypeAtomView {
+    /// Name of the atom.
+    pub name: String,
+    /// Mass of the atom.
+    pub mass: f64,
+    /// Charge of the atom.
+    pub charge: f64,
+    /// Mass of the atom in the B-state. `None` if the same as the A-state mass.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub mass_b: Option<f64>,
+    /// Charge of the atom in the B-state. `None` if the same as the A-state charge.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub charge_b: Option<f64>,
+    /// Index of the residue (in `MoleculeTypeView::residues`) this atom is part of.
+    pub residue_index: usize,
+    /// Element this atom belongs to.
+    #[cfg_attr(feature = "serde", serde(with = "crate::atom::element_serde"))]
+    pub element: Option<Element>,
+}
+
+/// Structure representing a residue of a molecule type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MoleculeTypeResidueView {
+    /// Name of the residue.
+    pub name: String,
+    /// Residue number as stored in the tpr file.
+    pub number: i32,
+    /// Insertion code of the residue. `None` if the residue has no insertion code.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub insertion_code: Option<char>,
+}
+
+/// Enum describing all supported interaction types.
+/// The names of the variants correspond to the names of the function types used by Gromacs.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    num_derive::FromPrimitive,
+    strum::EnumIter,
+    strum::EnumCount,
+    strum::IntoStaticStr,
+)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[allow(non_camel_case_types)]
+pub enum InteractionType {
+    F_BONDS = 0,
+    F_G96BONDS,
+    F_MORSE,
+    F_CUBICBONDS,
+    F_CONNBONDS,
+    F_HARMONIC,
+    F_FENEBONDS,
+    F_TABBONDS,
+    F_TABBONDSNC,
+    F_RESTRBONDS,
+    F_ANGLES,
+    F_G96ANGLES,
+    F_RESTRANGLES,
+    F_LINEAR_ANGLES,
+    F_CROSS_BOND_BONDS,
+    F_CROSS_BOND_ANGLES,
+    F_UREY_BRADLEY,
+    F_QUARTIC_ANGLES,
+    F_TABANGLES,
+    F_PDIHS,
+    F_RBDIHS,
+    F_RESTRDIHS,
+    F_CBTDIHS,
+    F_FOURDIHS,
+    F_IDIHS,
+    F_PIDIHS,
+    F_TABDIHS,
+    F_CMAP,
+    F_GB12_NOLONGERUSED,
+    F_GB13_NOLONGERUSED,
+    F_GB14_NOLONGERUSED,
+    F_GBPOL_NOLONGERUSED,
+    F_NPSOLVATION_NOLONGERUSED,
+    F_LJ14,
+    F_COUL14,
+    F_LJC14_Q,
+    F_LJC_PAIRS_NB,
+    F_LJ,
+    F_BHAM,
+    F_LJ_LR_NOLONGERUSED,
+    F_BHAM_LR_NOLONGERUSED,
+    F_DISPCORR,
+    F_COUL_SR,
+    F_COUL_LR_NOLONGERUSED,
+    F_RF_EXCL,
+    F_COUL_RECIP,
+    F_LJ_RECIP,
+    F_DPD,
+    F_POLARIZATION,
+    F_WATER_POL,
+    F_THOLE_POL,
+    F_ANHARM_POL,
+    F_POSRES,
+    F_FBPOSRES,
+    F_DISRES,
+    F_DISRESVIOL,
+    F_ORIRES,
+    F_ORIRESDEV,
+    F_ANGRES,
+    F_ANGRESZ,
+    F_DIHRES,
+    F_DIHRESVIOL,
+    F_CONSTR,
+    F_CONSTRNC,
+    F_SETTLE,
+    F_VSITE1,
+    F_VSITE2,
+    F_VSITE2FD,
+    F_VSITE3,
+    F_VSITE3FD,
+    F_VSITE3FAD,
+    F_VSITE3OUT,
+    F_VSITE4FD,
+    F_VSITE4FDN,
+    F_VSITEN,
+    F_COM_PULL,
+    F_DENSITYFITTING,
+    F_EQM,
+    F_EPOT,
+    F_EKIN,
+    F_ETOT,
+    F_ECONSERVED,
+    F_TEMP,
+    F_VTEMP_NOLONGERUSED,
+    F_PDISPCORR,
+    F_PRES,
+    F_DVDL_CONSTR,
+    F_DVDL,
+    F_DKDL,
+    F_DVDL_COUL,
+    F_DVDL_VDW,
+    F_DVDL_BONDED,
+    F_DVDL_RESTRAINT,
+    F_DVDL_TEMPERATURE,
+}
+
+/// Structure representing a restraint applied to a set of atoms.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Restraint {
+    /// Type of the restraint.
+    pub kind: RestraintKind,
+    /// Global indices of the restrained atoms.
+    /// Distance and orientation restraints involve two atoms, dihedral restraints four atoms.
+    pub atoms: Vec<usize>,
+}
+
+/// Enum representing the types of restraints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RestraintKind {
+    /// Distance restraint (`F_DISRES`).
+    Distance,
+    /// Orientation restraint (`F_ORIRES`).
+    Orientation,
+    /// Dihedral restraint (`F_DIHRES`).
+    Dihedral,
+}
+
+/// Structure representing a block of molecules of the same type.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MolBlockView {
+    /// Index of the molecule type the molecules of this block belong to.
+    pub molecule_type: usize,
+    /// Name of the molecule type.
+    pub name: String,
+    /// Number of molecules in the block.
+    pub n_molecules: usize,
+    /// Number of atoms in each molecule of the block, as stored in the tpr file.
+    /// Should be identical to the number of atoms of the molecule type.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub atoms_per_molecule: usize,
+    /// Number of reference positions for position restraints stored for the block.
+    /// If any atom of the block is restrained, Gromacs stores one reference position
+    /// for each atom of each molecule in the block. Zero if no atom of the block is restrained.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub posres_count: usize,
+    /// Number of reference positions for flat-bottomed position restraints stored for the block.
+    /// See `posres_count` for more information.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fbposres_count: usize,
+}
+
+/// Structure representing a single residue of the system.
+/// Can be obtained using [`TprTopology::residues`](crate::TprTopology::residues).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ResidueView {
+    /// Name of the residue.
+    pub name: String,
+    /// Sequential number of the residue (see `Atom::residue_number`).
+    pub number: i32,
+    /// Indices of the atoms belonging to the residue.
+    pub atoms: Range<usize>,
+}
+
+/// Structure describing the differences between the topologies of two tpr files.
+/// Can be obtained using [`TprFile::topology_diff`](crate::TprFile::topology_diff).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TopologyDiff {
+    /// Indices of atoms only present in the other topology.
+    pub added_atoms: Vec<usize>,
+    /// Indices of atoms only present in this topology.
+    pub removed_atoms: Vec<usize>,
+    /// Indices of atoms present in both topologies which differ in their properties or positions.
+    pub changed_atoms: Vec<usize>,
+    /// Bonds only present in the other topology.
+    pub added_bonds: Vec<Bond>,
+    /// Bonds only present in this topology.
+    pub removed_bonds: Vec<Bond>,
+}
+
+/// Structure representing the atom groups used by the simulation.
+///
+/// Gromacs stores the names of all index groups, but the atoms are only stored
+/// for groups which are used for one of the [`GroupType`]s.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SimulationGroups {
+    /// Names of all the groups.
+    pub group_names: Vec<String>,
+    /// Groups used for each group type (in the order of [`GroupType`]).
+    /// Each group is represented by an index into `group_names`.
+    pub groups: Vec<Vec<usize>>,
+    /// Group numbers of atoms for each group type (in the order of [`GroupType`]).
+    /// Each group number is an index into the corresponding vector of `groups`.
+    /// If the vector is empty, all atoms belong to the first group of the group type.
+    pub group_numbers: Vec<Vec<u32>>,
+}
+
+/// Enum representing the types of atom groups used by the simulation.
+/// The variants are ordered in the same way as the group types in the tpr file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GroupType {
+    /// Temperature-coupling groups.
+    TemperatureCoupling = 0,
+    /// Energy-output groups.
+    EnergyOutput,
+    /// Acceleration groups.
+    Acceleration,
+    /// Freeze groups.
+    Freeze,
+    /// First user-defined groups.
+    User1,
+    /// Second user-defined groups.
+    User2,
+    /// Center-of-mass motion removal groups.
+    MassCenterVelocityRemoval,
+    /// Groups written into the compressed trajectory.
+    CompressedPositionOutput,
+    /// Orientation-restraint fitting groups.
+    OrientationRestraintFit,
+    /// QM/MM groups.
+    QuantumMechanics,
 }
 
 /// Structure representing simulation box dimensions.
@@ -79,6 +598,22 @@ pub struct SimBox {
     pub simbox_v: [[f64; DIM]; DIM],
 }
 
+/// Enum representing the shape of the simulation box.
+#[derive(Debug, Clone, PartialEq, Eq, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BoxShape {
+    /// Orthogonal box with all sides of the same length.
+    Cubic,
+    /// Orthogonal box with sides of different lengths.
+    Rectangular,
+    /// Rhombic dodecahedron (both the xy-square and the xy-hexagon variant).
+    Dodecahedral,
+    /// Truncated octahedron.
+    Octahedral,
+    /// Any other triclinic box.
+    Triclinic,
+}
+
 /// Enum representing precision of the tpr file.
 #[derive(Debug, Clone, PartialEq, Eq, Copy)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -87,6 +622,14 @@ pub enum Precision {
     Double,
 }
 
+/// Enum representing a block of coordinates stored in the tpr file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoordBlock {
+    Positions,
+    Velocities,
+    Forces,
+}
+
 /// Structure representing an atom.
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -99,21 +642,68 @@ pub struct Atom {
     pub residue_name: String,
     /// Residue number. All residues are numbered sequentially, starting from 1.
     pub residue_number: i32,
+    /// Residue number as stored in the tpr file, i.e., the residue number
+    /// from the structure file used to generate the tpr file.
+    pub original_residue_number: i32,
     /// Mass of the atom.
     pub mass: f64,
     /// Charge of the atom.
     pub charge: f64,
+    /// Mass of the atom in the B-state of a free-energy calculation.
+    /// `None` if the B-state mass is the same as the A-state mass.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub mass_b: Option<f64>,
+    /// Charge of the atom in the B-state of a free-energy calculation.
+    /// `None` if the B-state charge is the same as the A-state charge.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub charge_b: Option<f64>,
     /// Element this atom belongs to.
+    /// With the `serde` feature enabled, the element is (de)serialized as its symbol.
+    #[cfg_attr(feature = "serde", serde(with = "crate::atom::element_serde"))]
     pub element: Option<Element>,
     /// Position of the atom.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     pub position: Option<[f64; 3]>,
     /// Velocity of the atom.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     pub velocity: Option<[f64; 3]>,
     /// Force acting on the atom.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     pub force: Option<[f64; 3]>,
+    /// Index of the molecule this atom is part of.
+    /// Molecules are indexed sequentially across all molecule blocks, starting from 0.
+    pub molecule_id: usize,
+    /// Chain identifier of the molecule this atom is part of.
+    /// Each molecule consisting of more than one residue (e.g., a protein or a nucleic acid chain)
+    /// is assigned the next identifier from the sequence `A-Z`, `a-z`, `0-9` (wrapping around after `9`).
+    /// Atoms of single-residue molecules (e.g., solvent, ions, lipids) have a blank (`' '`) chain identifier.
+    pub chain_id: char,
+    /// Insertion code of the residue this atom is part of.
+    /// `None` if the residue has no insertion code.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub insertion_code: Option<char>,
 }
 
 /// Structure representing a bond between atoms.
+/// Bonds read from the tpr file always store the smaller atom index in `atom1`.
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Bond {
@@ -0,0 +1,995 @@
+// Released under Apache License 2.0 / MIT License.
+// Copyright (c) 2024 Ladislav Bartos
+
+//! This file contains methods for working with the system topology.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    ops::Index,
+};
+
+use crate::{
+    errors::ParseTprError,
+    structures::{Atom, Bond, ResidueView, Restraint, SimBox, TprTopology},
+};
+
+impl TprTopology {
+    /// Remove duplicate bonds from the topology.
+    ///
+    /// ## Notes
+    /// - Bonds are compared after normalization, i.e., bonds `0-1` and `1-0` are considered identical.
+    /// - The first occurrence of each bond is kept and the order of the bonds is preserved.
+    pub fn deduplicate_bonds(&mut self) {
+        let mut visited = HashSet::with_capacity(self.bonds.len());
+        self.bonds.retain(|bond| {
+            let bond = Bond::new(bond.atom1, bond.atom2);
+            visited.insert((bond.atom1, bond.atom2))
+        });
+    }
+
+    /// Sort the bonds of the topology, making their order deterministic.
+    ///
+    /// ## Notes
+    /// - Each bond is normalized first, i.e., `atom1` is always lower than or equal to `atom2`.
+    /// - Bonds are then sorted by `atom1` and, for identical `atom1`, by `atom2`.
+    /// - Duplicate bonds are kept. Use [`TprTopology::deduplicate_bonds`] to remove them.
+    pub fn sort_bonds(&mut self) {
+        for bond in self.bonds.iter_mut() {
+            *bond = Bond::new(bond.atom1, bond.atom2);
+        }
+
+        self.bonds
+            .sort_unstable_by_key(|bond| (bond.atom1, bond.atom2));
+    }
+
+    /// Get the list of bonded neighbors for each atom of the system.
+    ///
+    /// ## Returns
+    /// Vector with one item per atom. Each item contains the sorted indices of the atoms
+    /// bonded to the corresponding atom. Duplicate bonds are only reported once.
+    pub fn adjacency(&self) -> Vec<Vec<usize>> {
+        let mut adjacency = vec![Vec::new(); self.atoms.len()];
+
+        for bond in self.bonds.iter() {
+            adjacency[bond.atom1].push(bond.atom2);
+            adjacency[bond.atom2].push(bond.atom1);
+        }
+
+        for neighbors in adjacency.iter_mut() {
+            neighbors.sort_unstable();
+            neighbors.dedup();
+        }
+
+        adjacency
+    }
+
+    /// Get the residues of the system.
+    ///
+    /// ## Returns
+    /// Vector of residues in the order in which they appear in the topology.
+    /// Each residue contains the range of indices of its atoms.
+    ///
+    /// ## Notes
+    /// - Atoms of a residue are always stored contiguously in the topology.
+    ///   A new residue starts whenever `Atom::residue_number` changes.
+    pub fn residues(&self) -> Vec<ResidueView> {
+        let mut residues: Vec<ResidueView> = Vec::new();
+
+        for (index, atom) in self.atoms.iter().enumerate() {
+            match residues.last_mut() {
+                Some(residue) if residue.number == atom.residue_number => {
+                    residue.atoms.end = index + 1
+                }
+                _ => residues.push(ResidueView {
+                    name: atom.residue_name.clone(),
+                    number: atom.residue_number,
+                    atoms: index..index + 1,
+                }),
+            }
+        }
+
+        residues
+    }
+
+    /// Get the atom with the specified Gromacs atom number.
+    ///
+    /// ## Parameters
+    /// - `number`: atom number (starting from 1)
+    ///
+    /// ## Returns
+    /// - Reference to the atom, if it exists.
+    /// - `None`, if `number` is lower than 1 or higher than the number of atoms.
+    ///
+    /// ## Notes
+    /// - Atoms are numbered sequentially, so `atom_by_number(n)` corresponds to `atoms[n - 1]`.
+    pub fn atom_by_number(&self, number: i32) -> Option<&Atom> {
+        let index = usize::try_from(number).ok()?.checked_sub(1)?;
+        self.atoms.get(index)
+    }
+
+    /// Select atoms belonging to residues with the specified name.
+    ///
+    /// ## Parameters
+    /// - `name`: name of the residue (case-sensitive)
+    ///
+    /// ## Returns
+    /// Sorted vector of indices (starting from 0) of the matching atoms.
+    pub fn atoms_in_residue_name(&self, name: &str) -> Vec<usize> {
+        self.select(|atom| atom.residue_name == name)
+    }
+
+    /// Select atoms with the specified name.
+    ///
+    /// ## Parameters
+    /// - `name`: name of the atom (case-sensitive)
+    ///
+    /// ## Returns
+    /// Sorted vector of indices (starting from 0) of the matching atoms.
+    pub fn atoms_with_name(&self, name: &str) -> Vec<usize> {
+        self.select(|atom| atom.atom_name == name)
+    }
+
+    /// Select atoms of water molecules.
+    ///
+    /// ## Returns
+    /// Sorted vector of indices (starting from 0) of atoms belonging to residues
+    /// with one of the [`WATER_RESIDUE_NAMES`](`crate::WATER_RESIDUE_NAMES`).
+    ///
+    /// ## Notes
+    /// - Water molecules are identified heuristically, based only on the names of their residues.
+    ///   Residue names are compared case-insensitively.
+    /// - To use a different set of residue names, use [`TprTopology::water_atom_indices_with`].
+    pub fn water_atom_indices(&self) -> Vec<usize> {
+        self.water_atom_indices_with(crate::WATER_RESIDUE_NAMES)
+    }
+
+    /// Select atoms of water molecules using a custom set of water residue names.
+    ///
+    /// ## Parameters
+    /// - `names`: names of the water residues (compared case-insensitively)
+    ///
+    /// ## Returns
+    /// Sorted vector of indices (starting from 0) of atoms belonging to residues with one of the `names`.
+    pub fn water_atom_indices_with(&self, names: &[&str]) -> Vec<usize> {
+        self.select(|atom| {
+            names
+                .iter()
+                .any(|name| atom.residue_name.eq_ignore_ascii_case(name))
+        })
+    }
+
+    /// Extract a part of the topology containing only the selected atoms.
+    ///
+    /// ## Parameters
+    /// - `indices`: indices of the atoms to keep (starting from 0)
+    ///
+    /// ## Returns
+    /// New topology with the selected atoms in the order in which they are listed in `indices`.
+    /// Atoms are renumbered sequentially, starting from 1.
+    ///
+    /// ## Notes
+    /// - Bonds, Urey-Bradley pairs, CMAP terms, and restraints are only kept if all of their atoms
+    ///   are selected. Their atom indices are remapped to the indices in the new topology.
+    /// - Residue numbers, molecule indices, and chain identifiers of the atoms are kept unchanged.
+    /// - If an index is listed multiple times, only its first occurrence is used.
+    ///
+    /// ## Panics
+    /// Panics if any of the indices is out of bounds.
+    ///
+    /// ## Example
+    /// Removing water molecules from the system:
+    /// ```no_run
+    /// use minitpr::TprFile;
+    ///
+    /// let tpr = TprFile::parse("topol.tpr").expect("Could not parse tpr file.");
+    /// let selected = (0..tpr.topology.atoms.len())
+    ///     .filter(|&index| tpr.topology.atoms[index].residue_name != "SOL")
+    ///     .collect::<Vec<usize>>();
+    /// let stripped = tpr.topology.subset(&selected);
+    /// ```
+    pub fn subset(&self, indices: &[usize]) -> TprTopology {
+        // mapping of the original atom indices to the new atom indices
+        let mut remap = vec![None; self.atoms.len()];
+        let mut atoms = Vec::with_capacity(indices.len());
+
+        for &index in indices {
+            if remap[index].is_some() {
+                continue;
+            }
+
+            remap[index] = Some(atoms.len());
+            let mut atom = self.atoms[index].clone();
+            atom.atom_number = atoms.len() as i32 + 1;
+            atoms.push(atom);
+        }
+
+        let remap_bond = |bond: &Bond| Some(Bond::new(remap[bond.atom1]?, remap[bond.atom2]?));
+        let remap_atoms = |atoms: &[usize]| -> Option<Vec<usize>> {
+            atoms.iter().map(|&index| remap[index]).collect()
+        };
+
+        TprTopology {
+            atoms,
+            bonds: self.bonds.iter().filter_map(remap_bond).collect(),
+            urey_bradley_pairs: self
+                .urey_bradley_pairs
+                .iter()
+                .filter_map(remap_bond)
+                .collect(),
+            cmap_terms: self
+                .cmap_terms
+                .iter()
+                .filter_map(|term| remap_atoms(term)?.try_into().ok())
+                .collect(),
+            restraints: self
+                .restraints
+                .iter()
+                .filter_map(|restraint| {
+                    Some(Restraint {
+                        kind: restraint.kind,
+                        atoms: remap_atoms(&restraint.atoms)?,
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    /// Append atoms and connectivity of another topology to this topology.
+    ///
+    /// ## Parameters
+    /// - `other`: topology to append
+    ///
+    /// ## Notes
+    /// - Atom numbers, residue numbers, and molecule indices of the appended atoms
+    ///   continue from the last atom, residue, and molecule of this topology.
+    ///   Original residue numbers and chain identifiers are kept unchanged.
+    /// - Atom indices of the appended bonds, Urey-Bradley pairs, CMAP terms, and restraints
+    ///   are offset by the number of atoms in this topology.
+    /// - Positions, velocities, and forces of the appended atoms are kept as they are.
+    /// - This is a topology-level operation. Headers, simulation boxes, molecule blocks,
+    ///   and other properties of the tpr files are not merged.
+    pub fn merge(&mut self, other: &TprTopology) {
+        let atom_offset = self.atoms.len();
+        let last = self.atoms.last();
+        let residue_offset = last.map_or(0, |atom| atom.residue_number);
+        let molecule_offset = last.map_or(0, |atom| atom.molecule_id + 1);
+
+        self.atoms.extend(other.atoms.iter().map(|atom| {
+            let mut atom = atom.clone();
+            atom.atom_number += atom_offset as i32;
+            atom.residue_number += residue_offset;
+            atom.molecule_id += molecule_offset;
+            atom
+        }));
+
+        let offset_bond =
+            |bond: &Bond| Bond::new(bond.atom1 + atom_offset, bond.atom2 + atom_offset);
+
+        self.bonds.extend(other.bonds.iter().map(offset_bond));
+        self.urey_bradley_pairs
+            .extend(other.urey_bradley_pairs.iter().map(offset_bond));
+        self.cmap_terms.extend(
+            other
+                .cmap_terms
+                .iter()
+                .map(|term| term.map(|index| index + atom_offset)),
+        );
+        self.restraints
+            .extend(other.restraints.iter().map(|restraint| {
+                Restraint {
+                    kind: restraint.kind,
+                    atoms: restraint
+                        .atoms
+                        .iter()
+                        .map(|index| index + atom_offset)
+                        .collect(),
+                }
+            }));
+    }
+
+    /// Get all bonds involving the specified atom.
+    ///
+    /// ## Parameters
+    /// - `atom_index`: index of the atom (starting from 0)
+    ///
+    /// ## Returns
+    /// Vector of references to bonds in which `atom1` or `atom2` is `atom_index`.
+    /// Bonds are returned in the order in which they appear in `TprTopology::bonds`.
+    pub fn bonds_of(&self, atom_index: usize) -> Vec<&Bond> {
+        self.bonds
+            .iter()
+            .filter(|bond| bond.atom1 == atom_index || bond.atom2 == atom_index)
+            .collect()
+    }
+
+    /// Get the number of atoms bonded to the specified atom.
+    ///
+    /// ## Parameters
+    /// - `atom_index`: index of the atom (starting from 0)
+    ///
+    /// ## Notes
+    /// - Each bonded neighbor is only counted once, even if there are duplicate bonds.
+    /// - If you need the degrees of many atoms, use [`TprTopology::adjacency`] instead.
+    pub fn degree(&self, atom_index: usize) -> usize {
+        let mut neighbors: Vec<usize> = self
+            .bonds_of(atom_index)
+            .into_iter()
+            .map(|bond| {
+                if bond.atom1 == atom_index {
+                    bond.atom2
+                } else {
+                    bond.atom1
+                }
+            })
+            .collect();
+
+        neighbors.sort_unstable();
+        neighbors.dedup();
+        neighbors.len()
+    }
+
+    /// Identify connected components of the bond graph.
+    ///
+    /// ## Returns
+    /// Vector with one item per atom containing the index of the connected component
+    /// the atom belongs to. Components are numbered sequentially, starting from 0,
+    /// in the order of their first atom. Atoms with no bonds form their own components.
+    ///
+    /// ## Notes
+    /// - Components are identified purely from the bonds, independently of the molecule blocks.
+    ///   Intermolecular bonds therefore merge molecules which are kept separate in the molecule
+    ///   blocks (and have different `Atom::molecule_id`) into a single component.
+    pub fn connected_components(&self) -> Vec<usize> {
+        /// Find the root of the set containing `index` while compressing the path.
+        fn find(parents: &mut [usize], mut index: usize) -> usize {
+            while parents[index] != index {
+                parents[index] = parents[parents[index]];
+                index = parents[index];
+            }
+            index
+        }
+
+        let mut parents: Vec<usize> = (0..self.atoms.len()).collect();
+
+        for bond in self.bonds.iter() {
+            let root1 = find(&mut parents, bond.atom1);
+            let root2 = find(&mut parents, bond.atom2);
+
+            // always keep the lower index as the root
+            if root1 < root2 {
+                parents[root2] = root1;
+            } else {
+                parents[root1] = root2;
+            }
+        }
+
+        let mut component_ids = vec![usize::MAX; self.atoms.len()];
+        let mut components = Vec::with_capacity(self.atoms.len());
+        let mut n_components = 0;
+
+        for index in 0..self.atoms.len() {
+            let root = find(&mut parents, index);
+            if component_ids[root] == usize::MAX {
+                component_ids[root] = n_components;
+                n_components += 1;
+            }
+
+            components.push(component_ids[root]);
+        }
+
+        components
+    }
+
+    /// Guess elements of atoms with unknown elements from their names.
+    ///
+    /// ## Notes
+    /// - Only atoms with `element` set to `None` are modified.
+    /// - The guessing is heuristic (see [`Atom::guess_element_from_name`]) and is never
+    ///   performed automatically while parsing the tpr file.
+    pub fn fill_missing_elements(&mut self) {
+        for atom in self.atoms.iter_mut().filter(|atom| atom.element.is_none()) {
+            atom.element = atom.guess_element_from_name();
+        }
+    }
+
+    /// Get positions of all atoms as a contiguous vector.
+    /// Returns `None` if any atom has no position.
+    pub fn positions(&self) -> Option<Vec<[f64; 3]>> {
+        self.atoms.iter().map(|atom| atom.position).collect()
+    }
+
+    /// Get velocities of all atoms as a contiguous vector.
+    /// Returns `None` if any atom has no velocity.
+    pub fn velocities(&self) -> Option<Vec<[f64; 3]>> {
+        self.atoms.iter().map(|atom| atom.velocity).collect()
+    }
+
+    /// Get forces acting on all atoms as a contiguous vector.
+    /// Returns `None` if any atom has no force.
+    pub fn forces(&self) -> Option<Vec<[f64; 3]>> {
+        self.atoms.iter().map(|atom| atom.force).collect()
+    }
+
+    /// Get positions of all atoms as a contiguous vector of single-precision values.
+    /// Returns `None` if any atom has no position.
+    ///
+    /// ## Notes
+    /// - Values read from single-precision tpr files are converted exactly.
+    ///   Values read from double-precision tpr files (see [`TprHeader::precision`](`crate::TprHeader::precision`))
+    ///   are rounded to the nearest single-precision value.
+    pub fn positions_f32(&self) -> Option<Vec<[f32; 3]>> {
+        self.atoms
+            .iter()
+            .map(|atom| atom.position.map(to_f32))
+            .collect()
+    }
+
+    /// Get velocities of all atoms as a contiguous vector of single-precision values.
+    /// Returns `None` if any atom has no velocity.
+    /// See [`TprTopology::positions_f32`] for more information.
+    pub fn velocities_f32(&self) -> Option<Vec<[f32; 3]>> {
+        self.atoms
+            .iter()
+            .map(|atom| atom.velocity.map(to_f32))
+            .collect()
+    }
+
+    /// Get forces acting on all atoms as a contiguous vector of single-precision values.
+    /// Returns `None` if any atom has no force.
+    /// See [`TprTopology::positions_f32`] for more information.
+    pub fn forces_f32(&self) -> Option<Vec<[f32; 3]>> {
+        self.atoms
+            .iter()
+            .map(|atom| atom.force.map(to_f32))
+            .collect()
+    }
+
+    /// Remove positions, velocities, and forces of all atoms.
+    ///
+    /// ## Notes
+    /// - Atoms and bonds are kept.
+    /// - Coordinates are stored inline in the atoms, so this does not reduce the size of the atoms.
+    ///   To also free the contiguous blocks of coordinates of a [`TprFile`](crate::TprFile),
+    ///   use [`TprFile::clear_coordinates`](crate::TprFile::clear_coordinates).
+    pub fn clear_coordinates(&mut self) {
+        for atom in self.atoms.iter_mut() {
+            atom.position = None;
+            atom.velocity = None;
+            atom.force = None;
+        }
+    }
+
+    /// Wrap positions of all atoms into the primary unit cell of the simulation box.
+    ///
+    /// ## Parameters
+    /// - `simbox`: simulation box to wrap the positions into
+    ///
+    /// ## Notes
+    /// - The full (triclinic) box matrix is used. Following the Gromacs convention,
+    ///   box vectors are subtracted starting from the last one, so that each coordinate
+    ///   `m` of a wrapped position lies within `[0, simbox.simbox[m][m])`.
+    /// - Atoms without a position are left untouched.
+    pub fn wrap_into_box(&mut self, simbox: &SimBox) {
+        for position in self
+            .atoms
+            .iter_mut()
+            .filter_map(|atom| atom.position.as_mut())
+        {
+            *position = simbox.wrap(*position);
+        }
+    }
+
+    /// Add bonds between atoms that are closer to each other than the specified cutoff.
+    ///
+    /// ## Parameters
+    /// - `cutoff`: maximal distance (in nm) between two atoms for them to be bonded
+    /// - `simbox`: simulation box; if provided, distances are calculated using
+    ///   the minimum image convention (the box can be triclinic)
+    ///
+    /// ## Notes
+    /// - This is a heuristic intended for systems with missing or incomplete bonds.
+    ///   It does not take elements or atom types into account.
+    /// - Bonds already present in the topology are not added again.
+    ///   New bonds are appended to `bonds` with the smaller atom index in `atom1`.
+    /// - Atoms without a position are skipped.
+    /// - Neighbors are searched for using a uniform grid, so the time required scales
+    ///   linearly with the number of atoms.
+    pub fn infer_bonds_by_distance(&mut self, cutoff: f64, simbox: Option<&SimBox>) {
+        if cutoff <= 0.0 || !cutoff.is_finite() {
+            return;
+        }
+
+        let grid = match simbox.and_then(|simbox| Some((simbox, simbox.reciprocal()?))) {
+            Some((simbox, reciprocal)) => Grid::periodic(simbox, reciprocal, cutoff),
+            None => Grid::open(cutoff),
+        };
+
+        let mut cells: HashMap<[i64; 3], Vec<usize>> = HashMap::new();
+        for (index, atom) in self.atoms.iter().enumerate() {
+            if let Some(position) = atom.position {
+                cells.entry(grid.cell(position)).or_default().push(index);
+            }
+        }
+
+        let existing = self
+            .bonds
+            .iter()
+            .map(|bond| {
+                let bond = Bond::new(bond.atom1, bond.atom2);
+                (bond.atom1, bond.atom2)
+            })
+            .collect::<HashSet<(usize, usize)>>();
+
+        let mut new_bonds = Vec::new();
+        for (cell, atoms) in cells.iter() {
+            for neighbor in grid.neighbors(*cell) {
+                let Some(neighbor_atoms) = cells.get(&neighbor) else {
+                    continue;
+                };
+
+                for &i in atoms {
+                    for &j in neighbor_atoms.iter().filter(|&&j| j > i) {
+                        let (Some(pos1), Some(pos2)) =
+                            (self.atoms[i].position, self.atoms[j].position)
+                        else {
+                            continue;
+                        };
+
+                        if grid.distance(pos1, pos2) <= cutoff && !existing.contains(&(i, j)) {
+                            new_bonds.push(Bond::new(i, j));
+                        }
+                    }
+                }
+            }
+        }
+
+        new_bonds.sort_by_key(|bond| (bond.atom1, bond.atom2));
+        self.bonds.extend(new_bonds);
+    }
+
+    /// Calculate the bounding box of the system.
+    ///
+    /// ## Returns
+    /// - Minimal and maximal corner of the axis-aligned box enclosing all atoms with a position.
+    /// - `None` if no atom has a position.
+    ///
+    /// ## Notes
+    /// - Atoms without a position are skipped.
+    pub fn bounding_box(&self) -> Option<([f64; 3], [f64; 3])> {
+        self.atoms
+            .iter()
+            .filter_map(|atom| atom.position)
+            .fold(None, |corners, position| match corners {
+                None => Some((position, position)),
+                Some((mut min, mut max)) => {
+                    for d in 0..3 {
+                        min[d] = min[d].min(position[d]);
+                        max[d] = max[d].max(position[d]);
+                    }
+                    Some((min, max))
+                }
+            })
+    }
+
+    /// Calculate the center of mass of the system.
+    ///
+    /// ## Returns
+    /// - Mass-weighted average of atom positions.
+    /// - `None` if any atom has no position or if the total mass of the atoms is zero.
+    pub fn center_of_mass(&self) -> Option<[f64; 3]> {
+        center_of_mass(self.atoms.iter())
+    }
+
+    /// Calculate the center of mass of the selected atoms.
+    ///
+    /// ## Parameters
+    /// - `indices`: indices of the atoms to use
+    ///
+    /// ## Returns
+    /// - Mass-weighted average of positions of the selected atoms.
+    /// - `None` if any selected atom does not exist or has no position,
+    ///   or if the total mass of the selected atoms is zero.
+    pub fn center_of_mass_of(&self, indices: &[usize]) -> Option<[f64; 3]> {
+        let atoms = indices
+            .iter()
+            .map(|&index| self.atoms.get(index))
+            .collect::<Option<Vec<&Atom>>>()?;
+
+        center_of_mass(atoms.into_iter())
+    }
+
+    /// Translate all atoms of the system.
+    ///
+    /// ## Parameters
+    /// - `shift`: vector to add to the position of each atom
+    ///
+    /// ## Notes
+    /// - Atoms without a position are left untouched.
+    pub fn translate(&mut self, shift: [f64; 3]) {
+        for position in self
+            .atoms
+            .iter_mut()
+            .filter_map(|atom| atom.position.as_mut())
+        {
+            for (x, s) in position.iter_mut().zip(shift) {
+                *x += s;
+            }
+        }
+    }
+
+    /// Translate all atoms of the system so that its center of mass is placed at the origin.
+    ///
+    /// ## Notes
+    /// - Atoms without a position are ignored when calculating the center of mass
+    ///   and are left untouched.
+    /// - If no atom has a position or the total mass of the atoms with a position is zero,
+    ///   the system is not translated.
+    pub fn center(&mut self) {
+        if let Some(com) = center_of_mass(self.atoms.iter().filter(|atom| atom.position.is_some()))
+        {
+            self.translate([-com[0], -com[1], -com[2]]);
+        }
+    }
+
+    /// Get the molecular formula of the system in Hill notation.
+    ///
+    /// ## Notes
+    /// - If the system contains carbon, carbon is listed first, hydrogen second,
+    ///   and all other elements follow in alphabetical order. Otherwise, all elements
+    ///   (including hydrogen) are listed in alphabetical order.
+    /// - Atoms with unknown elements are skipped.
+    ///
+    /// ## Example
+    /// For a system composed of three TIP3P water molecules, the formula is `H6O3`.
+    pub fn formula(&self) -> String {
+        formula(self.atoms.iter())
+    }
+
+    /// Get the molecular formula of the selected atoms in Hill notation.
+    ///
+    /// ## Parameters
+    /// - `indices`: indices of the atoms to use
+    ///
+    /// ## Returns
+    /// - Formula of the selected atoms (see [`TprTopology::formula`]).
+    /// - `None` if any selected atom does not exist.
+    pub fn formula_of(&self, indices: &[usize]) -> Option<String> {
+        let atoms = indices
+            .iter()
+            .map(|&index| self.atoms.get(index))
+            .collect::<Option<Vec<&Atom>>>()?;
+
+        Some(formula(atoms.into_iter()))
+    }
+
+    /// Check the integrity of the topology.
+    ///
+    /// ## Returns
+    /// - `Ok` if the topology is consistent.
+    /// - `ParseTprError::InvalidBond` if any bond refers to an atom that does not exist.
+    /// - `ParseTprError::NonSequentialAtomNumber` if the atoms are not numbered sequentially, starting from 1.
+    /// - `ParseTprError::NonMonotonicResidueNumber` if the residue number of any atom
+    ///   is lower than the residue number of the preceding atom.
+    ///
+    /// ## Notes
+    /// - Topologies read from tpr files are always consistent, unless the format of the tpr file
+    ///   is not properly supported. Use [`ParseOptions::strict`](crate::ParseOptions::strict)
+    ///   to validate the topology automatically after parsing.
+    pub fn validate(&self) -> Result<(), ParseTprError> {
+        if let Some(bond) = self
+            .bonds
+            .iter()
+            .find(|bond| bond.atom1 >= self.atoms.len() || bond.atom2 >= self.atoms.len())
+        {
+            return Err(ParseTprError::InvalidBond(bond.atom1, bond.atom2));
+        }
+
+        let mut previous_residue = i32::MIN;
+        for (index, atom) in self.atoms.iter().enumerate() {
+            if i64::from(atom.atom_number) != index as i64 + 1 {
+                return Err(ParseTprError::NonSequentialAtomNumber(
+                    index,
+                    atom.atom_number,
+                ));
+            }
+
+            if atom.residue_number < previous_residue {
+                return Err(ParseTprError::NonMonotonicResidueNumber(
+                    index,
+                    atom.residue_number,
+                ));
+            }
+
+            previous_residue = atom.residue_number;
+        }
+
+        Ok(())
+    }
+
+    /// Check whether two topologies are identical up to the specified tolerance.
+    ///
+    /// ## Parameters
+    /// - `other`: topology to compare with
+    /// - `epsilon`: maximal absolute difference between the floating-point properties of the atoms
+    ///
+    /// ## Notes
+    /// - Atoms are compared using [`Atom::approx_eq`].
+    /// - Bonds, Urey-Bradley pairs, CMAP terms, and restraints must be identical, including their order.
+    pub fn approx_eq(&self, other: &TprTopology, epsilon: f64) -> bool {
+        self.atoms.len() == other.atoms.len()
+            && self
+                .atoms
+                .iter()
+                .zip(other.atoms.iter())
+                .all(|(a, b)| a.approx_eq(b, epsilon))
+            && self.bonds == other.bonds
+            && self.urey_bradley_pairs == other.urey_bradley_pairs
+            && self.cmap_terms == other.cmap_terms
+            && self.restraints == other.restraints
+    }
+
+    /// Calculate a stable hash of the content of the topology.
+    ///
+    /// ## Returns
+    /// 64-bit digest of the atoms and bonds of the topology.
+    ///
+    /// ## Notes
+    /// - The names, residue names, residue numbers, masses, charges, and elements of the atoms are hashed.
+    ///   Positions, velocities, and forces are **not** hashed.
+    /// - Bonds are hashed as a set of normalized bonds, i.e., the order of the bonds,
+    ///   the order of atoms in each bond, and duplicate bonds do not affect the hash.
+    /// - The hash is calculated using the FNV-1a algorithm and is independent
+    ///   of the platform and of the version of the Rust compiler.
+    ///   It is therefore suitable for caching and change-detection.
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = Fnv1a::new();
+
+        hasher.write_usize(self.atoms.len());
+        for atom in self.atoms.iter() {
+            hasher.write_str(&atom.atom_name);
+            hasher.write_str(&atom.residue_name);
+            hasher.write(&atom.residue_number.to_le_bytes());
+            hasher.write(&atom.mass.to_bits().to_le_bytes());
+            hasher.write(&atom.charge.to_bits().to_le_bytes());
+            hasher.write(&atom.atomic_number().unwrap_or(0).to_le_bytes());
+        }
+
+        let mut bonds = self
+            .bonds
+            .iter()
+            .map(|bond| {
+                let bond = Bond::new(bond.atom1, bond.atom2);
+                (bond.atom1, bond.atom2)
+            })
+            .collect::<Vec<_>>();
+        bonds.sort_unstable();
+        bonds.dedup();
+
+        hasher.write_usize(bonds.len());
+        for (atom1, atom2) in bonds {
+            hasher.write_usize(atom1);
+            hasher.write_usize(atom2);
+        }
+
+        hasher.finish()
+    }
+
+    /// Get indices of atoms matching the provided condition.
+    fn select(&self, condition: impl Fn(&Atom) -> bool) -> Vec<usize> {
+        self.atoms
+            .iter()
+            .enumerate()
+            .filter_map(|(index, atom)| condition(atom).then_some(index))
+            .collect()
+    }
+}
+
+/// 64-bit FNV-1a hasher producing digests that are stable across platforms and compiler versions.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    fn new() -> Self {
+        Fnv1a(Self::OFFSET_BASIS)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    /// Write `usize` as 64-bit little-endian integer so that the hash does not depend on the platform.
+    fn write_usize(&mut self, value: usize) {
+        self.write(&(value as u64).to_le_bytes());
+    }
+
+    /// Write string prefixed by its length so that e.g. `("AB", "C")` and `("A", "BC")` differ.
+    fn write_str(&mut self, value: &str) {
+        self.write_usize(value.len());
+        self.write(value.as_bytes());
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Uniform grid used for searching for neighboring atoms.
+enum Grid<'a> {
+    /// Grid in cartesian coordinates without periodic boundary conditions.
+    Open { cell_size: f64 },
+    /// Grid in fractional coordinates of a periodic simulation box.
+    Periodic {
+        simbox: &'a SimBox,
+        reciprocal: [[f64; 3]; 3],
+        n_cells: [i64; 3],
+    },
+}
+
+impl<'a> Grid<'a> {
+    /// Create a grid without periodic boundary conditions.
+    fn open(cutoff: f64) -> Self {
+        Grid::Open { cell_size: cutoff }
+    }
+
+    /// Create a grid spanning the periodic simulation box.
+    /// The number of cells in each direction is chosen so that the distance
+    /// between the opposite faces of a cell is at least `cutoff`.
+    fn periodic(simbox: &'a SimBox, reciprocal: [[f64; 3]; 3], cutoff: f64) -> Self {
+        let n_cells = reciprocal.map(|vector| {
+            let width = 1.0 / vector.iter().map(|x| x * x).sum::<f64>().sqrt();
+            ((width / cutoff).floor() as i64).max(1)
+        });
+
+        Grid::Periodic {
+            simbox,
+            reciprocal,
+            n_cells,
+        }
+    }
+
+    /// Get the cell the position belongs to.
+    fn cell(&self, position: [f64; 3]) -> [i64; 3] {
+        match self {
+            Grid::Open { cell_size } => position.map(|x| (x / cell_size).floor() as i64),
+            Grid::Periodic {
+                reciprocal,
+                n_cells,
+                ..
+            } => std::array::from_fn(|m| {
+                let fractional: f64 = (0..3).map(|d| position[d] * reciprocal[m][d]).sum();
+                let fractional = fractional - fractional.floor();
+                ((fractional * n_cells[m] as f64) as i64).clamp(0, n_cells[m] - 1)
+            }),
+        }
+    }
+
+    /// Get the cell itself and all cells adjacent to it. Each cell is listed only once.
+    fn neighbors(&self, cell: [i64; 3]) -> Vec<[i64; 3]> {
+        let mut neighbors = Vec::with_capacity(27);
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    let mut neighbor = [cell[0] + dx, cell[1] + dy, cell[2] + dz];
+                    if let Grid::Periodic { n_cells, .. } = self {
+                        for (x, n) in neighbor.iter_mut().zip(n_cells) {
+                            *x = x.rem_euclid(*n);
+                        }
+                    }
+
+                    if !neighbors.contains(&neighbor) {
+                        neighbors.push(neighbor);
+                    }
+                }
+            }
+        }
+
+        neighbors
+    }
+
+    /// Calculate the distance between two positions.
+    fn distance(&self, pos1: [f64; 3], pos2: [f64; 3]) -> f64 {
+        let dx = match self {
+            Grid::Open { .. } => [pos2[0] - pos1[0], pos2[1] - pos1[1], pos2[2] - pos1[2]],
+            Grid::Periodic { simbox, .. } => simbox.min_image(pos1, pos2),
+        };
+
+        dx.iter().map(|x| x * x).sum::<f64>().sqrt()
+    }
+}
+
+/// Get the molecular formula of the provided atoms in Hill notation.
+/// Atoms with unknown elements are skipped.
+fn formula<'a>(atoms: impl Iterator<Item = &'a Atom>) -> String {
+    let mut counts: BTreeMap<&'static str, usize> = BTreeMap::new();
+    for symbol in atoms.filter_map(|atom| atom.element_symbol()) {
+        *counts.entry(symbol).or_insert(0) += 1;
+    }
+
+    let mut formula = String::new();
+    let mut push = |symbol: &str, count: usize| {
+        formula.push_str(symbol);
+        if count > 1 {
+            formula.push_str(&count.to_string());
+        }
+    };
+
+    // Hill notation: carbon first, then hydrogen, then the rest alphabetically
+    if let Some(carbon) = counts.remove("C") {
+        push("C", carbon);
+        if let Some(hydrogen) = counts.remove("H") {
+            push("H", hydrogen);
+        }
+    }
+
+    for (symbol, count) in counts {
+        push(symbol, count);
+    }
+
+    formula
+}
+
+/// Convert a vector to single precision.
+fn to_f32(vector: [f64; 3]) -> [f32; 3] {
+    vector.map(|x| x as f32)
+}
+
+/// Calculate the center of mass of the provided atoms.
+/// Returns `None` if any atom has no position or if the total mass is zero.
+fn center_of_mass<'a>(atoms: impl Iterator<Item = &'a Atom>) -> Option<[f64; 3]> {
+    let mut sum = [0.0; 3];
+    let mut total_mass = 0.0;
+
+    for atom in atoms {
+        let position = atom.position?;
+        for (s, x) in sum.iter_mut().zip(position) {
+            *s += atom.mass * x;
+        }
+        total_mass += atom.mass;
+    }
+
+    if total_mass == 0.0 {
+        return None;
+    }
+
+    Some(sum.map(|s| s / total_mass))
+}
+
+impl Index<usize> for TprTopology {
+    type Output = Atom;
+
+    /// Get the atom with the specified index.
+    ///
+    /// ## Panics
+    /// Panics if the index is out of bounds.
+    #[inline(always)]
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.atoms[index]
+    }
+}
+
+impl<'a> IntoIterator for &'a TprTopology {
+    type Item = &'a Atom;
+    type IntoIter = std::slice::Iter<'a, Atom>;
+
+    /// Iterate over the atoms of the topology.
+    #[inline(always)]
+    fn into_iter(self) -> Self::IntoIter {
+        self.atoms.iter()
+    }
+}
+
+impl Bond {
+    /// Create a new bond between two atoms.
+    /// The smaller of the two atom indices is always stored in `atom1`.
+    pub fn new(atom1: usize, atom2: usize) -> Self {
+        Bond {
+            atom1: atom1.min(atom2),
+            atom2: atom1.max(atom2),
+        }
+    }
+}
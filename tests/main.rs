@@ -15,12 +15,18 @@ mod test_utilities {
                 atom_number: $atom_number,
                 residue_name: $residue_name.to_owned(),
                 residue_number: $residue_number,
+                original_residue_number: $residue_number,
                 mass: $mass,
                 charge: $charge,
+                mass_b: None,
+                charge_b: None,
                 element: $element,
                 position: $position,
                 velocity: $velocity,
                 force: $force,
+                molecule_id: 0,
+                chain_id: ' ',
+                insertion_code: None,
             }
         };
     }
@@ -65,7 +71,12 @@ mod test_utilities {
 #[cfg(test)]
 mod tests {
     use super::test_utilities::*;
-    use minitpr::{Atom, Bond, Element, Precision, TprFile};
+    use minitpr::{
+        errors::{ParseTprError, ParseWarning, TprResult},
+        Atom, AtomIndices, Bond, BoxShape, CoordBlock, Element, GroupType, Interaction,
+        InteractionType, ParseOptions, ParseStage, Precision, RestraintKind, SimBox, TprFile,
+        TprParser,
+    };
 
     use float_cmp::assert_approx_eq;
 
@@ -101,6 +112,25 @@ mod tests {
         assert_approx_eq!(f64, simbox.simbox[2][1], 0.0000, epsilon = 0.000001);
         assert_approx_eq!(f64, simbox.simbox[2][2], 11.3344, epsilon = 0.000001);
 
+        assert_approx_eq!(
+            f64,
+            simbox.volume(),
+            9.2122 * 9.2122 * 11.3344,
+            epsilon = 0.0001
+        );
+
+        let lengths = simbox.lengths();
+        assert_approx_eq!(f64, lengths[0], 9.2122, epsilon = 0.000001);
+        assert_approx_eq!(f64, lengths[1], 9.2122, epsilon = 0.000001);
+        assert_approx_eq!(f64, lengths[2], 11.3344, epsilon = 0.000001);
+
+        for angle in simbox.angles() {
+            assert_approx_eq!(f64, angle, 90.0, epsilon = 0.000001);
+        }
+
+        assert!(simbox.is_orthogonal(0.0));
+        assert_eq!(simbox.shape(), BoxShape::Rectangular);
+
         assert_approx_eq!(f64, simbox.simbox_rel[0][0], 0.0000, epsilon = 0.000001);
         assert_approx_eq!(f64, simbox.simbox_rel[0][1], 0.0000, epsilon = 0.000001);
         assert_approx_eq!(f64, simbox.simbox_rel[0][2], 0.0000, epsilon = 0.000001);
@@ -384,7 +414,30 @@ mod tests {
 
     #[test]
     fn empty_fail() {
-        assert!(TprFile::parse("tests/test_files/empty.tpr").is_err());
+        match TprFile::parse("tests/test_files/empty.tpr") {
+            Err(ParseTprError::EmptyFile) => (),
+            Err(e) => panic!("Unexpected error returned: {}", e),
+            Ok(_) => panic!("Parsing should have failed."),
+        }
+    }
+
+    #[test]
+    fn short_fail() {
+        // first 10 bytes of `water_2021.tpr`
+        match TprFile::parse("tests/test_files/water_2021_short.tpr") {
+            Err(ParseTprError::NotTpr) => (),
+            Err(e) => panic!("Unexpected error returned: {}", e),
+            Ok(_) => panic!("Parsing should have failed."),
+        }
+    }
+
+    #[test]
+    fn not_tpr_fail() {
+        match TprFile::parse("tests/test_files/small_aa_2021.yaml") {
+            Err(ParseTprError::NotTpr) => (),
+            Err(e) => panic!("Unexpected error returned: {}", e),
+            Ok(_) => panic!("Parsing should have failed."),
+        }
     }
 
     enum GmxVersion {
@@ -3245,6 +3298,27 @@ mod tests {
         assert_approx_eq!(f64, simbox.simbox[2][1], -1.69043, epsilon = 0.000001);
         assert_approx_eq!(f64, simbox.simbox[2][2], 2.22778, epsilon = 0.000001);
 
+        assert_approx_eq!(
+            f64,
+            simbox.volume(),
+            5.29700 * 4.78912 * 2.22778,
+            epsilon = 0.0001
+        );
+
+        let lengths = simbox.lengths();
+        assert_approx_eq!(f64, lengths[0], 5.297, epsilon = 0.00001);
+        assert_approx_eq!(f64, lengths[1], 4.863, epsilon = 0.00001);
+        assert_approx_eq!(f64, lengths[2], 2.976, epsilon = 0.00001);
+
+        let angles = simbox.angles();
+        assert_approx_eq!(f64, angles[0], 120.0, epsilon = 0.001);
+        assert_approx_eq!(f64, angles[1], 70.0, epsilon = 0.001);
+        assert_approx_eq!(f64, angles[2], 80.0, epsilon = 0.001);
+
+        assert!(!simbox.is_orthogonal(0.1));
+        assert!(simbox.is_orthogonal(2.0));
+        assert_eq!(simbox.shape(), BoxShape::Triclinic);
+
         assert_approx_eq!(f64, simbox.simbox_rel[0][0], 0.0000, epsilon = 0.000001);
         assert_approx_eq!(f64, simbox.simbox_rel[0][1], 0.0000, epsilon = 0.000001);
         assert_approx_eq!(f64, simbox.simbox_rel[0][2], 0.0000, epsilon = 0.000001);
@@ -3328,55 +3402,3005 @@ mod tests {
         let expected_atom_names = ["OH2", "H1", "H2", "OH2", "H1", "H2", "OH2", "H1", "H2"];
         let expected_bonds = [(0, 1), (0, 2), (3, 4), (3, 5), (6, 7), (6, 8)];
 
-        for (atom, expected) in tpr
-            .topology
-            .atoms
-            .iter()
-            .zip(expected_atom_names.into_iter())
-        {
+        for (atom, expected) in tpr.topology.atoms.iter().zip(expected_atom_names) {
             assert_eq!(atom.atom_name, expected);
         }
 
-        for (bond, expected) in tpr.topology.bonds.iter().zip(expected_bonds.into_iter()) {
+        for (bond, expected) in tpr.topology.bonds.iter().zip(expected_bonds) {
             assert_eq!(bond.atom1, expected.0);
             assert_eq!(bond.atom2, expected.1);
         }
     }
-}
 
-#[cfg(test)]
-#[cfg(feature = "serde")]
-mod tests_serde {
-    use super::test_utilities::*;
-    use minitpr::TprFile;
-    use std::fs::read_to_string;
+    #[test]
+    fn adjacency() {
+        let tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+        let adjacency = tpr.topology.adjacency();
+
+        assert_eq!(adjacency.len(), 9);
+        assert_eq!(adjacency[0], vec![1, 2]);
+        assert_eq!(adjacency[1], vec![0]);
+        assert_eq!(adjacency[2], vec![0]);
+        assert_eq!(adjacency[6], vec![7, 8]);
+
+        // bond!(0, 1) and bond!(1, 2) are both present in this file
+        let tpr = TprFile::parse("tests/test_files/double_2023.tpr").unwrap();
+        let adjacency = tpr.topology.adjacency();
+
+        assert_eq!(adjacency.len(), 16844);
+        assert_eq!(adjacency[1], vec![0, 2, 4]);
+        for (i, neighbors) in adjacency.iter().enumerate() {
+            assert!(neighbors.windows(2).all(|w| w[0] < w[1]));
+            for &j in neighbors {
+                assert!(adjacency[j].contains(&i));
+            }
+        }
+    }
 
     #[test]
-    fn to_yaml() {
+    fn connected_components() {
+        let tpr = TprFile::parse("tests/test_files/small_cg_2021.tpr").unwrap();
+        let components = tpr.topology.connected_components();
+
+        // without intermolecular bonds, components correspond to molecules
+        for (atom, component) in tpr.topology.atoms.iter().zip(components) {
+            assert_eq!(atom.molecule_id, component);
+        }
+
+        let tpr = TprFile::parse("tests/test_files/small_cg_2021_intermolecular.tpr").unwrap();
+        let components = tpr.topology.connected_components();
+
+        assert_eq!(components.len(), 77);
+        // peptide and the first lipid are connected
+        assert!(components[..54].iter().all(|&c| c == 0));
+        // second lipid is connected to one water bead
+        assert!(components[54..66].iter().all(|&c| c == 1));
+        assert_eq!(components[67], 1);
+        // two water beads are connected
+        assert_eq!(components[73], components[74]);
+        assert_eq!(components[76], 10);
+    }
+
+    #[test]
+    fn center_of_mass() {
+        let tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+
+        let com = tpr.topology.center_of_mass().unwrap();
+        assert_approx_eq!(f64, com[0], 9.140345, epsilon = 0.00001);
+        assert_approx_eq!(f64, com[1], 12.335462, epsilon = 0.00001);
+        assert_approx_eq!(f64, com[2], 0.494065, epsilon = 0.00001);
+
+        let com = tpr.topology.center_of_mass_of(&[0, 1, 2]).unwrap();
+        assert_approx_eq!(f64, com[0], 9.381511, epsilon = 0.00001);
+        assert_approx_eq!(f64, com[1], 12.530847, epsilon = 0.00001);
+        assert_approx_eq!(f64, com[2], 0.500485, epsilon = 0.00001);
+
+        // selection of a single atom
+        let com = tpr.topology.center_of_mass_of(&[3]).unwrap();
+        assert_approx_eq!(f64, com[0], 8.965, epsilon = 0.00001);
+        assert_approx_eq!(f64, com[1], 12.835, epsilon = 0.00001);
+        assert_approx_eq!(f64, com[2], 0.039, epsilon = 0.00001);
+
+        // empty selection has zero mass
+        assert!(tpr.topology.center_of_mass_of(&[]).is_none());
+        // atom does not exist
+        assert!(tpr.topology.center_of_mass_of(&[0, 9]).is_none());
+
+        // atoms without positions
+        let mut tpr = tpr;
+        tpr.topology.atoms[4].position = None;
+        assert!(tpr.topology.center_of_mass().is_none());
+        assert!(tpr.topology.center_of_mass_of(&[3, 4, 5]).is_none());
+        assert!(tpr.topology.center_of_mass_of(&[6, 7, 8]).is_some());
+    }
+
+    #[test]
+    fn atom_elements() {
+        let tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+        let atoms = &tpr.topology.atoms;
+
+        assert!(!atoms[0].is_hydrogen());
+        assert!(atoms[1].is_hydrogen());
+        assert!(atoms[2].is_hydrogen());
+
+        assert_eq!(atoms[0].element_symbol(), Some("O"));
+        assert_eq!(atoms[1].element_symbol(), Some("H"));
+        assert_eq!(atoms[0].atomic_number(), Some(8));
+        assert_eq!(atoms[1].atomic_number(), Some(1));
+
+        let tpr = TprFile::parse("tests/test_files/small_cg_2021.tpr").unwrap();
+        let atoms = &tpr.topology.atoms;
+
+        assert!(atoms.iter().all(|atom| !atom.is_hydrogen()));
+        assert!(atoms.iter().all(|atom| atom.element_symbol().is_none()));
+        assert!(atoms.iter().all(|atom| atom.atomic_number().is_none()));
+
+        // hydrogen identified from mass
+        let hydrogen = atom!("H", 1, "RES", 1, 1.008, 0.0, None, None, None, None);
+        assert!(hydrogen.is_hydrogen());
+        let heavy = atom!("X", 1, "RES", 1, 12.011, 0.0, None, None, None, None);
+        assert!(!heavy.is_hydrogen());
+    }
+
+    #[test]
+    fn guess_elements() {
+        let guess = |name: &str, mass: f64| {
+            atom!(name, 1, "RES", 1, mass, 0.0, None, None, None, None).guess_element_from_name()
+        };
+
+        assert_eq!(guess("CA", 12.011), Some(Element::C));
+        assert_eq!(guess("CA", 40.08), Some(Element::Ca));
+        assert_eq!(guess("HD11", 1.008), Some(Element::H));
+        assert_eq!(guess("OW", 15.9994), Some(Element::O));
+        assert_eq!(guess("CL-", 35.453), Some(Element::Cl));
+        assert_eq!(guess("NA", 22.99), Some(Element::Na));
+        assert_eq!(guess("SOD", 22.99), Some(Element::S));
+        assert_eq!(guess("Zn", 65.38), Some(Element::Zn));
+        assert_eq!(guess("1HB", 1.008), None);
+        assert_eq!(guess("", 1.008), None);
+
+        // elements are already known for all-atom systems
+        let mut tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+        tpr.topology.atoms[0].element = None;
+        tpr.topology.atoms[1].element = None;
+        tpr.topology.fill_missing_elements();
+
+        let expected = [Element::O, Element::H, Element::H];
+        for (atom, element) in tpr.topology.atoms.iter().zip(expected.iter().cycle()) {
+            assert_eq!(atom.element, Some(*element));
+        }
+
+        let mut tpr = TprFile::parse("tests/test_files/small_cg_2021.tpr").unwrap();
+        tpr.topology.fill_missing_elements();
+        assert_eq!(tpr.topology.atoms[76].element, Some(Element::Cl));
+    }
+
+    #[test]
+    fn zero_atomic_number() {
+        // atomic number of the first hydrogen of the water molecule type set to 0
+        let tpr = TprFile::parse("tests/test_files/water_2021_zero_atomic_number.tpr").unwrap();
+
+        assert_eq!(tpr.topology.atoms.len(), 9);
+        let expected = [Some(Element::O), None, Some(Element::H)];
+        for (atom, element) in tpr.topology.atoms.iter().zip(expected.iter().cycle()) {
+            assert_eq!(atom.element, *element);
+        }
+    }
+
+    #[test]
+    fn unknown_interaction_type_fail() {
+        // number of the last interaction type set to 250
+        match TprFile::parse("tests/test_files/water_2021_unknown_interaction.tpr") {
+            Err(ParseTprError::UnknownInteractionType(250)) => (),
+            Err(e) => panic!("Unexpected error returned: {}", e),
+            Ok(_) => panic!("Parsing should have failed."),
+        }
+    }
+
+    #[test]
+    fn box_shapes() {
+        let make_box = |simbox: [[f64; 3]; 3]| SimBox {
+            simbox,
+            simbox_rel: [[0.0; 3]; 3],
+            simbox_v: [[0.0; 3]; 3],
+        };
+
+        let d = 5.0f64;
+        let cubic = make_box([[d, 0.0, 0.0], [0.0, d, 0.0], [0.0, 0.0, d]]);
+        assert!(cubic.is_orthogonal(0.0));
+        assert_eq!(cubic.shape(), BoxShape::Cubic);
+
+        let dodecahedron_square = make_box([
+            [d, 0.0, 0.0],
+            [0.0, d, 0.0],
+            [d / 2.0, d / 2.0, d * 2.0f64.sqrt() / 2.0],
+        ]);
+        assert!(!dodecahedron_square.is_orthogonal(0.001));
+        assert_eq!(dodecahedron_square.shape(), BoxShape::Dodecahedral);
+
+        let dodecahedron_hexagon = make_box([
+            [d, 0.0, 0.0],
+            [d / 2.0, d * 3.0f64.sqrt() / 2.0, 0.0],
+            [d / 2.0, d * 3.0f64.sqrt() / 6.0, d * 6.0f64.sqrt() / 3.0],
+        ]);
+        assert_eq!(dodecahedron_hexagon.shape(), BoxShape::Dodecahedral);
+
+        let octahedron = make_box([
+            [d, 0.0, 0.0],
+            [d / 3.0, d * 2.0 * 2.0f64.sqrt() / 3.0, 0.0],
+            [-d / 3.0, d * 2.0f64.sqrt() / 3.0, d * 6.0f64.sqrt() / 3.0],
+        ]);
+        assert_eq!(octahedron.shape(), BoxShape::Octahedral);
+    }
+
+    #[test]
+    fn contiguous_coordinates() {
+        let tpr = TprFile::parse("tests/test_files/small_cg_2021.tpr").unwrap();
+
+        let positions = tpr.topology.positions().unwrap();
+        let velocities = tpr.topology.velocities().unwrap();
+        assert!(tpr.topology.forces().is_none());
+
+        assert_eq!(positions.len(), 77);
+        assert_eq!(velocities.len(), 77);
+        for (i, atom) in tpr.topology.atoms.iter().enumerate() {
+            assert_eq!(atom.position.unwrap(), positions[i]);
+            assert_eq!(atom.velocity.unwrap(), velocities[i]);
+        }
+
+        let mut tpr = tpr;
+        tpr.topology.atoms[10].position = None;
+        assert!(tpr.topology.positions().is_none());
+    }
+
+    #[test]
+    fn has_positions() {
+        let tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+        assert!(tpr.header.has_positions);
+        assert!(tpr.header.has_velocities);
+        assert!(tpr
+            .topology
+            .atoms
+            .iter()
+            .all(|atom| atom.position.is_some()));
+        assert!(tpr
+            .topology
+            .atoms
+            .iter()
+            .all(|atom| atom.velocity.is_some()));
+
+        // header bits for positions and velocities cleared
+        let tpr = TprFile::parse("tests/test_files/water_2021_no_coordinates.tpr").unwrap();
+        assert!(!tpr.header.has_positions);
+        assert!(!tpr.header.has_velocities);
+        assert!(!tpr.header.has_forces);
+        assert!(tpr.header.has_box);
+        assert_eq!(tpr.topology.atoms.len(), 9);
+        assert!(tpr
+            .topology
+            .atoms
+            .iter()
+            .all(|atom| atom.position.is_none()));
+        assert!(tpr
+            .topology
+            .atoms
+            .iter()
+            .all(|atom| atom.velocity.is_none()));
+        assert!(tpr.topology.positions().is_none());
+    }
+
+    #[test]
+    fn index_groups() {
         let tpr = TprFile::parse("tests/test_files/small_aa_2021.tpr").unwrap();
 
-        let string = serde_yaml::to_string(&tpr).unwrap();
-        let expected = read_to_string("tests/test_files/small_aa_2021.yaml").unwrap();
+        assert_eq!(tpr.groups.group_names.len(), 24);
+        assert_eq!(tpr.groups.group_names[0], "System");
+        assert_eq!(tpr.groups.group_names[23], "rest");
 
-        assert_eq!(string, expected);
+        let groups = tpr.index_groups();
+        let names: Vec<&str> = groups.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["Protein", "Membrane", "W_ION", "rest", "Protein_Membrane"]
+        );
+
+        assert_eq!(groups[0].1, (0..44).collect::<Vec<usize>>());
+        assert_eq!(groups[1].1, (44..178).collect::<Vec<usize>>());
+        assert_eq!(groups[2].1, (178..182).collect::<Vec<usize>>());
+        assert_eq!(groups[3].1, (0..182).collect::<Vec<usize>>());
+        assert_eq!(groups[4].1, (0..178).collect::<Vec<usize>>());
+
+        let tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+        assert_eq!(tpr.groups.group_names, ["System", "Other", "TIP3", "rest"]);
+        assert_eq!(
+            tpr.index_groups(),
+            vec![(String::from("rest"), (0..9).collect::<Vec<usize>>())]
+        );
     }
 
     #[test]
-    fn from_yaml() {
-        let expected = TprFile::parse("tests/test_files/small_aa_2021.tpr").unwrap();
-        let from_yaml: TprFile =
-            serde_yaml::from_str(&read_to_string("tests/test_files/small_aa_2021.yaml").unwrap())
-                .unwrap();
+    fn group_types() {
+        // small_aa_2021.tpr modified to use `energygrps = W_ION Protein Membrane` and `freezegrps = Membrane`
+        let tpr = TprFile::parse("tests/test_files/small_aa_2021_groups.tpr").unwrap();
 
-        for (a, e) in from_yaml
+        assert_eq!(
+            tpr.groups.names_of(GroupType::TemperatureCoupling),
+            ["Protein", "Membrane", "W_ION"]
+        );
+        assert_eq!(
+            tpr.groups.names_of(GroupType::EnergyOutput),
+            ["W_ION", "Protein", "Membrane"]
+        );
+        assert_eq!(tpr.groups.names_of(GroupType::Acceleration), ["rest"]);
+        assert_eq!(tpr.groups.names_of(GroupType::Freeze), ["Membrane", "rest"]);
+        assert_eq!(
+            tpr.groups.names_of(GroupType::MassCenterVelocityRemoval),
+            ["Protein_Membrane", "W_ION"]
+        );
+
+        let energy = tpr.groups.group_numbers_of(GroupType::EnergyOutput, 182);
+        assert!(energy[..44].iter().all(|&group| group == 1));
+        assert!(energy[44..178].iter().all(|&group| group == 2));
+        assert!(energy[178..].iter().all(|&group| group == 0));
+
+        let freeze = tpr.groups.group_numbers_of(GroupType::Freeze, 182);
+        assert!(freeze[..44].iter().all(|&group| group == 1));
+        assert!(freeze[44..178].iter().all(|&group| group == 0));
+        assert!(freeze[178..].iter().all(|&group| group == 1));
+
+        assert_eq!(
+            tpr.groups.group_numbers_of(GroupType::Acceleration, 182),
+            vec![0; 182]
+        );
+    }
+
+    #[test]
+    fn temperature_coupling_groups() {
+        let tpr = TprFile::parse("tests/test_files/small_aa_2021.tpr").unwrap();
+
+        let names = tpr.temperature_coupling_group_names();
+        assert_eq!(names, ["Protein", "Membrane", "W_ION"]);
+        assert_eq!(names.len(), tpr.header.n_coupling_groups as usize);
+
+        let groups = tpr.temperature_coupling_groups();
+        assert_eq!(groups.len(), tpr.topology.atoms.len());
+        assert!(groups[0..44].iter().all(|&g| g == 0));
+        assert!(groups[44..178].iter().all(|&g| g == 1));
+        assert!(groups[178..182].iter().all(|&g| g == 2));
+
+        let tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+        assert_eq!(tpr.temperature_coupling_group_names(), ["rest"]);
+        assert_eq!(
+            tpr.temperature_coupling_group_names().len(),
+            tpr.header.n_coupling_groups as usize
+        );
+        assert_eq!(tpr.temperature_coupling_groups(), vec![0; 9]);
+    }
+
+    #[test]
+    fn energy_groups() {
+        let tpr = TprFile::parse("tests/test_files/small_aa_2021.tpr").unwrap();
+        assert_eq!(tpr.energy_group_names(), ["rest"]);
+        assert_eq!(tpr.energy_groups(), vec![0; 182]);
+
+        let tpr = TprFile::parse("tests/test_files/small_cg_2021.tpr").unwrap();
+        assert_eq!(tpr.energy_group_names(), ["rest"]);
+        assert_eq!(tpr.energy_groups(), vec![0; tpr.topology.atoms.len()]);
+    }
+
+    #[test]
+    fn energy_groups_multiple() {
+        // small_aa_2021.tpr modified to use `energygrps = W_ION Protein Membrane` and `freezegrps = Membrane`
+        let tpr = TprFile::parse("tests/test_files/small_aa_2021_groups.tpr").unwrap();
+        assert_eq!(tpr.energy_group_names(), ["W_ION", "Protein", "Membrane"]);
+
+        let groups = tpr.energy_groups();
+        assert_eq!(groups.len(), 182);
+        assert!(groups[..44].iter().all(|&group| group == 1));
+        assert!(groups[44..178].iter().all(|&group| group == 2));
+        assert!(groups[178..].iter().all(|&group| group == 0));
+
+        for (atom, &group) in tpr.topology.atoms.iter().zip(groups.iter()) {
+            let expected = match atom.residue_name.as_str() {
+                "POPC" => "Membrane",
+                "SOL" | "CL" => "W_ION",
+                _ => "Protein",
+            };
+            assert_eq!(tpr.energy_group_names()[group as usize], expected);
+        }
+
+        // the freeze groups are not returned
+        assert_ne!(
+            tpr.energy_group_names(),
+            tpr.groups.names_of(GroupType::Freeze)
+        );
+        assert_ne!(groups, tpr.groups.group_numbers_of(GroupType::Freeze, 182));
+        assert!(!tpr.energy_group_names().contains(&"rest"));
+
+        // the temperature-coupling groups are not affected
+        let original = TprFile::parse("tests/test_files/small_aa_2021.tpr").unwrap();
+        assert_eq!(
+            tpr.groups
+                .group_numbers_of(GroupType::TemperatureCoupling, 182),
+            original
+                .groups
+                .group_numbers_of(GroupType::TemperatureCoupling, 182)
+        );
+        assert!(tpr.topology.approx_eq(&original.topology, 0.0));
+    }
+
+    #[test]
+    fn insertion_codes() {
+        for file in [
+            "tests/test_files/small_aa_2021.tpr",
+            "tests/test_files/water_2021.tpr",
+            "tests/test_files/double_2023.tpr",
+        ] {
+            let tpr = TprFile::parse(file).unwrap();
+            assert!(tpr
+                .topology
+                .atoms
+                .iter()
+                .all(|atom| atom.insertion_code.is_none()));
+        }
+    }
+
+    #[test]
+    fn original_residue_numbers() {
+        let tpr = TprFile::parse("tests/test_files/small_cg_2021.tpr").unwrap();
+
+        for atom in tpr.topology.atoms.iter() {
+            if atom.residue_number <= 21 {
+                assert_eq!(atom.original_residue_number, atom.residue_number);
+            } else {
+                assert_eq!(atom.original_residue_number, 1);
+            }
+        }
+
+        let tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+        let residues: Vec<(i32, i32)> = tpr
             .topology
             .atoms
             .iter()
-            .zip(expected.topology.atoms.iter())
-        {
-            test_eq_atom(a, e);
+            .map(|atom| (atom.residue_number, atom.original_residue_number))
+            .collect();
+        assert_eq!(
+            residues,
+            [
+                (1, 1),
+                (1, 1),
+                (1, 1),
+                (2, 1),
+                (2, 1),
+                (2, 1),
+                (3, 1),
+                (3, 1),
+                (3, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn bstate() {
+        let tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+        assert!(tpr
+            .topology
+            .atoms
+            .iter()
+            .all(|atom| atom.mass_b.is_none() && atom.charge_b.is_none()));
+
+        let tpr = TprFile::parse("tests/test_files/water_2021_bstate.tpr").unwrap();
+        for (i, atom) in tpr.topology.atoms.iter().enumerate() {
+            if i % 3 == 0 {
+                assert_approx_eq!(f64, atom.mass_b.unwrap(), 18.0);
+                assert_approx_eq!(f64, atom.charge_b.unwrap(), -1.0);
+            } else {
+                assert!(atom.mass_b.is_none());
+                assert!(atom.charge_b.is_none());
+            }
+        }
+    }
+
+    #[test]
+    fn bonds_normalized() {
+        for file in [
+            "tests/test_files/small_aa_2021.tpr",
+            "tests/test_files/small_cg_2021.tpr",
+            "tests/test_files/large_2021_aa.tpr",
+            "tests/test_files/water_2021.tpr",
+            "tests/test_files/double_2023.tpr",
+        ] {
+            let tpr = TprFile::parse(file).unwrap();
+            assert!(tpr
+                .topology
+                .bonds
+                .iter()
+                .all(|bond| bond.atom1 < bond.atom2));
         }
 
-        assert_eq!(from_yaml.topology.bonds, expected.topology.bonds);
+        let tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+        assert!(tpr.topology.bonds.contains(&bond!(0, 1)));
+        assert!(tpr.topology.bonds.contains(&bond!(0, 2)));
+        assert!(!tpr.topology.bonds.contains(&bond!(1, 0)));
+
+        assert_eq!(Bond::new(7, 3), bond!(3, 7));
+        assert_eq!(Bond::new(3, 7), bond!(3, 7));
+    }
+
+    #[test]
+    fn deduplicate_bonds() {
+        let mut tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+        tpr.topology.bonds.push(bond!(1, 0));
+        tpr.topology.bonds.insert(0, bond!(0, 2));
+        tpr.topology.bonds.push(bond!(3, 4));
+        assert_eq!(tpr.topology.bonds.len(), 9);
+
+        tpr.topology.deduplicate_bonds();
+        assert_eq!(
+            tpr.topology.bonds,
+            vec![
+                bond!(0, 2),
+                bond!(0, 1),
+                bond!(3, 4),
+                bond!(3, 5),
+                bond!(6, 7),
+                bond!(6, 8)
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_with_options_deduplicate_bonds() {
+        let options = ParseOptions {
+            deduplicate_bonds: true,
+            ..Default::default()
+        };
+
+        for file in [
+            "tests/test_files/small_aa_2021.tpr",
+            "tests/test_files/small_cg_2021_intermolecular.tpr",
+            "tests/test_files/water_2021.tpr",
+        ] {
+            let tpr = TprFile::parse(file).unwrap();
+            let default = TprFile::parse_with_options(file, &ParseOptions::default()).unwrap();
+            let deduplicated = TprFile::parse_with_options(file, &options).unwrap();
+
+            // test files contain no duplicate bonds
+            assert_eq!(tpr.topology.bonds, default.topology.bonds);
+            assert_eq!(tpr.topology.bonds, deduplicated.topology.bonds);
+        }
+    }
+
+    #[test]
+    fn sort_bonds() {
+        let mut tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+        tpr.topology.bonds.insert(0, bond!(7, 6));
+        tpr.topology.bonds.push(bond!(1, 0));
+        tpr.topology.bonds.reverse();
+
+        tpr.topology.sort_bonds();
+        assert_eq!(
+            tpr.topology.bonds,
+            vec![
+                bond!(0, 1),
+                bond!(0, 1),
+                bond!(0, 2),
+                bond!(3, 4),
+                bond!(3, 5),
+                bond!(6, 7),
+                bond!(6, 7),
+                bond!(6, 8)
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_with_options_sort_bonds() {
+        for file in [
+            "tests/test_files/small_aa_2021.tpr",
+            "tests/test_files/small_cg_2021_intermolecular.tpr",
+            "tests/test_files/water_2021.tpr",
+        ] {
+            let tpr = TprFile::parse(file).unwrap();
+            let sorted = TprParser::new().sort_bonds(true).parse(file).unwrap();
+
+            assert_eq!(tpr.topology.bonds.len(), sorted.topology.bonds.len());
+            assert!(sorted.topology.bonds.iter().all(|b| b.atom1 <= b.atom2));
+            assert!(sorted
+                .topology
+                .bonds
+                .windows(2)
+                .all(|w| (w[0].atom1, w[0].atom2) <= (w[1].atom1, w[1].atom2)));
+
+            let mut expected = tpr.topology.clone();
+            expected.sort_bonds();
+            assert_eq!(expected.bonds, sorted.topology.bonds);
+        }
+    }
+
+    #[test]
+    fn content_hash() {
+        let tpr = TprFile::parse("tests/test_files/small_aa_2021.tpr").unwrap();
+        let hash = tpr.topology.content_hash();
+
+        // stable across parses and independent of coordinates
+        let no_coordinates = TprParser::new()
+            .coordinates(false)
+            .parse("tests/test_files/small_aa_2021.tpr")
+            .unwrap();
+        assert_eq!(no_coordinates.topology.content_hash(), hash);
+
+        // independent of the order and orientation of bonds and of duplicates
+        let mut shuffled = tpr.topology.clone();
+        shuffled.bonds.reverse();
+        for bond in shuffled.bonds.iter_mut() {
+            std::mem::swap(&mut bond.atom1, &mut bond.atom2);
+        }
+        shuffled.bonds.push(tpr.topology.bonds[0].clone());
+        assert_eq!(shuffled.content_hash(), hash);
+
+        // sensitive to changes in atoms and bonds
+        let mut renamed = tpr.topology.clone();
+        renamed.atoms[10].atom_name.push('X');
+        assert_ne!(renamed.content_hash(), hash);
+
+        let mut recharged = tpr.topology.clone();
+        recharged.atoms[10].charge += 0.001;
+        assert_ne!(recharged.content_hash(), hash);
+
+        let mut unbonded = tpr.topology.clone();
+        unbonded.bonds.pop();
+        assert_ne!(unbonded.content_hash(), hash);
+
+        let other = TprFile::parse("tests/test_files/small_cg_2021.tpr").unwrap();
+        assert_ne!(other.topology.content_hash(), hash);
+    }
+
+    #[test]
+    fn bonds_of() {
+        let tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+
+        assert_eq!(tpr.topology.bonds_of(3), vec![&bond!(3, 4), &bond!(3, 5)]);
+        assert_eq!(tpr.topology.bonds_of(5), vec![&bond!(3, 5)]);
+        assert!(tpr.topology.bonds_of(100).is_empty());
+
+        assert_eq!(tpr.topology.degree(0), 2);
+        assert_eq!(tpr.topology.degree(1), 1);
+        assert_eq!(tpr.topology.degree(100), 0);
+    }
+
+    #[test]
+    fn degree_matches_adjacency() {
+        let tpr = TprFile::parse("tests/test_files/small_aa_2021.tpr").unwrap();
+        let adjacency = tpr.topology.adjacency();
+
+        for (i, neighbors) in adjacency.iter().enumerate() {
+            assert_eq!(tpr.topology.degree(i), neighbors.len());
+        }
+    }
+
+    #[test]
+    fn residues() {
+        let tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+        let residues = tpr.topology.residues();
+
+        assert_eq!(residues.len(), 3);
+        for (i, residue) in residues.iter().enumerate() {
+            assert_eq!(residue.name, "TIP3");
+            assert_eq!(residue.number, i as i32 + 1);
+            assert_eq!(residue.atoms, 3 * i..3 * (i + 1));
+        }
+
+        let tpr = TprFile::parse("tests/test_files/small_aa_2021.tpr").unwrap();
+        let residues = tpr.topology.residues();
+
+        assert_eq!(
+            residues.len() as i32,
+            tpr.topology.atoms.last().unwrap().residue_number
+        );
+        assert_eq!(residues.first().unwrap().atoms.start, 0);
+        assert_eq!(residues.last().unwrap().atoms.end, tpr.topology.atoms.len());
+
+        for window in residues.windows(2) {
+            assert_eq!(window[0].atoms.end, window[1].atoms.start);
+        }
+
+        for residue in residues.iter() {
+            for atom in &tpr.topology.atoms[residue.atoms.clone()] {
+                assert_eq!(atom.residue_name, residue.name);
+                assert_eq!(atom.residue_number, residue.number);
+            }
+        }
+    }
+
+    #[test]
+    fn topology_index() {
+        let tpr = TprFile::parse("tests/test_files/small_aa_2021.tpr").unwrap();
+
+        for i in 0..tpr.topology.atoms.len() {
+            test_eq_atom(&tpr.topology[i], &tpr.topology.atoms[i]);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn topology_index_out_of_bounds() {
+        let tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+        let _ = &tpr.topology[9];
+    }
+
+    #[test]
+    fn topology_into_iter() {
+        let tpr = TprFile::parse("tests/test_files/small_aa_2021.tpr").unwrap();
+
+        let mut n_atoms = 0;
+        for atom in &tpr.topology {
+            test_eq_atom(atom, &tpr.topology.atoms[n_atoms]);
+            n_atoms += 1;
+        }
+        assert_eq!(n_atoms, tpr.topology.atoms.len());
+
+        let names: Vec<&str> = (&tpr.topology)
+            .into_iter()
+            .take(3)
+            .map(|atom| atom.atom_name.as_str())
+            .collect();
+        assert_eq!(names, ["N", "H1", "H2"]);
+    }
+
+    #[test]
+    fn header_display() {
+        let tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+
+        let expected = "Gromacs version: VERSION 2021.4
+Tpr version: 122 (generation 28)
+Precision: single
+Number of atoms: 9
+Simulation box: yes
+Positions: yes
+Velocities: yes
+Forces: no";
+
+        assert_eq!(tpr.header.to_string(), expected);
+    }
+
+    #[test]
+    fn summary() {
+        let tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+
+        let expected = "System name: Three water molecules
+Gromacs version: VERSION 2021.4
+Tpr version: 122 (generation 28)
+Precision: single
+Number of atoms: 9
+Simulation box: yes
+Positions: yes
+Velocities: yes
+Forces: no
+Number of residues: 3
+Number of molecules: 3
+Number of bonds: 6";
+
+        assert_eq!(tpr.summary(), expected);
+    }
+
+    #[test]
+    fn truncated_fail() {
+        // first 400 bytes of `water_2021.tpr`
+        match TprFile::parse("tests/test_files/water_2021_truncated.tpr") {
+            Err(ParseTprError::Truncated {
+                parsed_header,
+                kind,
+                ..
+            }) => {
+                assert_eq!(parsed_header.gromacs_version, "VERSION 2021.4");
+                assert_eq!(parsed_header.n_atoms, 9);
+                assert_eq!(kind, std::io::ErrorKind::UnexpectedEof);
+            }
+            Err(e) => panic!("Unexpected error returned: {}", e),
+            Ok(_) => panic!("Parsing should have failed."),
+        }
+    }
+
+    #[test]
+    fn truncated_coordinates_fail() {
+        // `small_cg_5.tpr` ending in the middle of the 21st velocity vector
+        match TprFile::parse("tests/test_files/small_cg_5_truncated_velocities.tpr") {
+            Err(ParseTprError::TruncatedCoordinates {
+                block,
+                read,
+                expected,
+            }) => {
+                assert_eq!(block, CoordBlock::Velocities);
+                assert_eq!(read, 20);
+                assert_eq!(expected, 77);
+            }
+            Err(e) => panic!("Unexpected error returned: {}", e),
+            Ok(_) => panic!("Parsing should have failed."),
+        }
+
+        // coordinates are not read at all
+        let tpr = TprParser::new()
+            .coordinates(false)
+            .parse("tests/test_files/small_cg_5_truncated_velocities.tpr")
+            .unwrap();
+        assert_eq!(tpr.topology.atoms.len(), 77);
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        // first byte of the residue name `TIP3` replaced with 0xFF
+        let tpr = TprFile::parse("tests/test_files/water_2021_invalid_utf8.tpr").unwrap();
+
+        for atom in tpr.topology.atoms.iter() {
+            assert_eq!(atom.residue_name, "\u{FFFD}IP3");
+        }
+
+        assert_eq!(tpr.topology.atoms[0].atom_name, "OH2");
+        assert_eq!(tpr.topology.bonds.len(), 6);
+    }
+
+    #[test]
+    fn implausible_length_fail() {
+        // length of the first string in the symbol table set to 2^40
+        match TprFile::parse("tests/test_files/water_2021_implausible_length.tpr") {
+            Err(ParseTprError::ImplausibleLength(1099511627776)) => (),
+            Err(e) => panic!("Unexpected error returned: {}", e),
+            Ok(_) => panic!("Parsing should have failed."),
+        }
+    }
+
+    #[test]
+    fn corrupted_no_panic() {
+        let original = std::fs::read("tests/test_files/water_2021.tpr").unwrap();
+        let path =
+            std::env::temp_dir().join(format!("minitpr_corrupted_{}.tpr", std::process::id()));
+
+        // overwrite every 4 consecutive bytes of the file with suspicious values
+        for value in [i32::MAX, -1] {
+            for offset in 0..original.len() - 4 {
+                let mut data = original.clone();
+                data[offset..offset + 4].copy_from_slice(&value.to_be_bytes());
+                std::fs::write(&path, &data).unwrap();
+
+                // parsing must not panic or attempt huge allocations
+                let _ = TprFile::parse(&path);
+            }
+        }
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn untested_version_fail() {
+        // version of the tpr file set to 200
+        match TprFile::parse("tests/test_files/water_2021_version_200.tpr") {
+            Err(ParseTprError::UntestedVersion(200)) => (),
+            Err(e) => panic!("Unexpected error returned: {}", e),
+            Ok(_) => panic!("Parsing should have failed."),
+        }
+    }
+
+    #[test]
+    fn untested_version_allowed() {
+        let options = ParseOptions {
+            allow_untested_version: true,
+            ..Default::default()
+        };
+
+        // the file is parsed as version 200 which does not match its actual layout,
+        // but the parsing must at least be attempted
+        match TprFile::parse_with_options("tests/test_files/water_2021_version_200.tpr", &options) {
+            Err(ParseTprError::UntestedVersion(_)) => panic!("Version check should be skipped."),
+            Err(ParseTprError::Truncated { parsed_header, .. }) => {
+                assert_eq!(parsed_header.tpr_version, 200)
+            }
+            Err(e) => panic!("Unexpected error returned: {}", e),
+            Ok(_) => panic!("Parsing should have failed."),
+        }
+    }
+
+    #[test]
+    fn body_size_extra_bytes_fail() {
+        // 8 zero bytes appended to the end of the file
+        match TprFile::parse("tests/test_files/water_2021_extra_bytes.tpr") {
+            Err(ParseTprError::BodySizeMismatch {
+                expected: 3155,
+                actual: 3164,
+            }) => (),
+            Err(e) => panic!("Unexpected error returned: {}", e),
+            Ok(_) => panic!("Parsing should have failed."),
+        }
+    }
+
+    #[test]
+    fn body_size_too_small_fail() {
+        // body size in the header set to 1000 bytes
+        match TprFile::parse("tests/test_files/water_2021_small_body_size.tpr") {
+            Err(ParseTprError::BodySizeMismatch {
+                expected: 1000,
+                actual,
+            }) => assert!(actual > 1000),
+            Err(e) => panic!("Unexpected error returned: {}", e),
+            Ok(_) => panic!("Parsing should have failed."),
+        }
+    }
+
+    #[test]
+    fn symbol_table() {
+        let tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+        assert!(tpr.symbol_table.is_none());
+
+        let options = ParseOptions {
+            keep_symbol_table: true,
+            ..Default::default()
+        };
+
+        let tpr = TprFile::parse_with_options("tests/test_files/water_2021.tpr", &options).unwrap();
+        let symbols = tpr.symbol_table.unwrap();
+
+        assert_eq!(symbols.len(), 29);
+        assert_eq!(symbols[0], "HAL1");
+        for name in [
+            "Three water molecules",
+            "TIP3",
+            "OH2",
+            "H1",
+            "H2",
+            "System",
+            "rest",
+        ] {
+            assert!(symbols.iter().any(|symbol| symbol == name));
+        }
+    }
+
+    #[test]
+    fn formula() {
+        let tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+        assert_eq!(tpr.topology.formula(), "H6O3");
+        assert_eq!(tpr.topology.formula_of(&[0, 1, 2]).unwrap(), "H2O");
+        assert_eq!(tpr.topology.formula_of(&[0]).unwrap(), "O");
+        assert_eq!(tpr.topology.formula_of(&[]).unwrap(), "");
+        assert!(tpr.topology.formula_of(&[0, 9]).is_none());
+
+        let tpr = TprFile::parse("tests/test_files/small_aa_2021.tpr").unwrap();
+        assert_eq!(tpr.topology.formula(), "C54H110ClN4O12P");
+
+        // coarse-grained beads have no elements
+        let tpr = TprFile::parse("tests/test_files/small_cg_2021.tpr").unwrap();
+        assert_eq!(tpr.topology.formula(), "");
+
+        // atoms with unknown elements are skipped
+        let mut tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+        tpr.topology.atoms[1].element = None;
+        assert_eq!(tpr.topology.formula(), "H5O3");
+    }
+
+    #[test]
+    fn wrap_into_box_triclinic() {
+        let mut tpr = TprFile::parse("tests/test_files/triclinic_2021.tpr").unwrap();
+        let simbox = tpr.simbox.clone().unwrap();
+        let [a, b, c] = simbox.simbox;
+
+        // move the atoms out of the box by various combinations of box vectors
+        for (i, atom) in tpr.topology.atoms.iter_mut().enumerate() {
+            let shift = [
+                (i % 3) as f64 - 1.0,
+                (i % 5) as f64 - 2.0,
+                (i % 7) as f64 - 3.0,
+            ];
+            let position = atom.position.as_mut().unwrap();
+            for d in 0..3 {
+                position[d] += shift[0] * a[d] + shift[1] * b[d] + shift[2] * c[d];
+            }
+        }
+
+        let original = tpr.topology.positions().unwrap();
+        tpr.topology.wrap_into_box(&simbox);
+
+        for (wrapped, original) in tpr.topology.positions().unwrap().iter().zip(original) {
+            for (d, coord) in wrapped.iter().enumerate() {
+                assert!(*coord >= 0.0 && *coord < simbox.simbox[d][d]);
+            }
+
+            // the displacement must be a combination of box vectors with integer coefficients
+            let diff = [
+                wrapped[0] - original[0],
+                wrapped[1] - original[1],
+                wrapped[2] - original[2],
+            ];
+            let nc = diff[2] / c[2];
+            let nb = (diff[1] - nc * c[1]) / b[1];
+            let na = (diff[0] - nc * c[0] - nb * b[0]) / a[0];
+            for n in [na, nb, nc] {
+                assert_approx_eq!(f64, n, n.round(), epsilon = 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn wrap_into_box_no_position() {
+        let mut tpr = TprFile::parse("tests/test_files/triclinic_2021.tpr").unwrap();
+        let simbox = tpr.simbox.clone().unwrap();
+        tpr.topology.atoms[0].position = None;
+        tpr.topology.wrap_into_box(&simbox);
+
+        assert!(tpr.topology.atoms[0].position.is_none());
+        assert!(tpr.topology.atoms[1].position.is_some());
+    }
+
+    #[test]
+    fn min_image_triclinic() {
+        let tpr = TprFile::parse("tests/test_files/triclinic_2021.tpr").unwrap();
+        let simbox = tpr.simbox.unwrap();
+        let [a, b, c] = simbox.simbox;
+
+        // displacement by a combination of box vectors and a small vector
+        let point1 = tpr.topology.atoms[0].position.unwrap();
+        let delta = [0.1, -0.2, 0.15];
+        let point2: [f64; 3] =
+            std::array::from_fn(|d| point1[d] + 2.0 * a[d] - b[d] - 3.0 * c[d] + delta[d]);
+
+        let dx = simbox.min_image(point1, point2);
+        for (x, y) in dx.iter().zip(delta) {
+            assert_approx_eq!(f64, *x, y, epsilon = 1e-6);
+        }
+
+        let dx = simbox.min_image(point2, point1);
+        for (x, y) in dx.iter().zip(delta) {
+            assert_approx_eq!(f64, *x, -y, epsilon = 1e-6);
+        }
+
+        // compare with a brute-force search over periodic images
+        let norm = |v: [f64; 3]| (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+        let positions = tpr.topology.positions().unwrap();
+        for (i, point1) in positions.iter().enumerate().step_by(37) {
+            for point2 in positions.iter().skip(i).step_by(53) {
+                let dx = simbox.min_image(*point1, *point2);
+
+                let mut shortest = f64::INFINITY;
+                for na in -3..=3 {
+                    for nb in -3..=3 {
+                        for nc in -3..=3 {
+                            let image: [f64; 3] = std::array::from_fn(|d| {
+                                point2[d] - point1[d]
+                                    + na as f64 * a[d]
+                                    + nb as f64 * b[d]
+                                    + nc as f64 * c[d]
+                            });
+                            shortest = shortest.min(norm(image));
+                        }
+                    }
+                }
+
+                assert_approx_eq!(f64, norm(dx), shortest, epsilon = 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn bounding_box() {
+        let mut tpr = TprFile::parse("tests/test_files/small_aa_2021.tpr").unwrap();
+        let (min, max) = tpr.topology.bounding_box().unwrap();
+
+        for atom in tpr.topology.atoms.iter() {
+            let position = atom.position.unwrap();
+            for d in 0..3 {
+                assert!(position[d] >= min[d] && position[d] <= max[d]);
+            }
+        }
+
+        for d in 0..3 {
+            assert!(tpr
+                .topology
+                .atoms
+                .iter()
+                .any(|atom| atom.position.unwrap()[d] == min[d]));
+            assert!(tpr
+                .topology
+                .atoms
+                .iter()
+                .any(|atom| atom.position.unwrap()[d] == max[d]));
+        }
+
+        // atoms without positions are skipped
+        for atom in tpr.topology.atoms.iter_mut().skip(1) {
+            atom.position = None;
+        }
+        let position = tpr.topology.atoms[0].position.unwrap();
+        assert_eq!(tpr.topology.bounding_box(), Some((position, position)));
+
+        tpr.topology.atoms[0].position = None;
+        assert!(tpr.topology.bounding_box().is_none());
+    }
+
+    #[test]
+    fn translate() {
+        let mut tpr = TprFile::parse("tests/test_files/small_aa_2021.tpr").unwrap();
+        let original = tpr.topology.positions().unwrap();
+        tpr.topology.atoms[1].position = None;
+
+        tpr.topology.translate([1.0, -2.0, 0.5]);
+
+        assert!(tpr.topology.atoms[1].position.is_none());
+        for (i, atom) in tpr
+            .topology
+            .atoms
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != 1)
+        {
+            let position = atom.position.unwrap();
+            assert_approx_eq!(f64, position[0], original[i][0] + 1.0);
+            assert_approx_eq!(f64, position[1], original[i][1] - 2.0);
+            assert_approx_eq!(f64, position[2], original[i][2] + 0.5);
+        }
+    }
+
+    #[test]
+    fn center() {
+        let mut tpr = TprFile::parse("tests/test_files/small_aa_2021.tpr").unwrap();
+        tpr.topology.center();
+
+        let com = tpr.topology.center_of_mass().unwrap();
+        for x in com {
+            assert_approx_eq!(f64, x, 0.0, epsilon = 1e-9);
+        }
+    }
+
+    #[test]
+    fn center_missing_positions() {
+        let mut tpr = TprFile::parse("tests/test_files/small_aa_2021.tpr").unwrap();
+        tpr.topology.atoms[0].position = None;
+        tpr.topology.center();
+
+        assert!(tpr.topology.atoms[0].position.is_none());
+        let indices = (1..tpr.topology.atoms.len()).collect::<Vec<usize>>();
+        let com = tpr.topology.center_of_mass_of(&indices).unwrap();
+        for x in com {
+            assert_approx_eq!(f64, x, 0.0, epsilon = 1e-9);
+        }
+    }
+
+    #[test]
+    fn urey_bradley_pairs() {
+        for file in [
+            "tests/test_files/small_aa_2016.tpr",
+            "tests/test_files/small_aa_2021.tpr",
+            "tests/test_files/small_aa_5.tpr",
+        ] {
+            let tpr = TprFile::parse(file).unwrap();
+            let pairs = &tpr.topology.urey_bradley_pairs;
+            assert_eq!(pairs.len(), 256);
+            assert_eq!(pairs[0], Bond::new(45, 46));
+            assert_eq!(pairs[6], Bond::new(44, 49));
+
+            // each pair must be separated by exactly two bonds
+            let adjacency = tpr.topology.adjacency();
+            for pair in pairs {
+                assert!(!adjacency[pair.atom1].contains(&pair.atom2));
+                assert!(adjacency[pair.atom1]
+                    .iter()
+                    .any(|&middle| adjacency[middle].contains(&pair.atom2)));
+            }
+
+            // Urey-Bradley pairs are not bonds
+            assert_eq!(tpr.topology.bonds.len(), 178);
+        }
+
+        let tpr = TprFile::parse("tests/test_files/small_cg_2021.tpr").unwrap();
+        assert!(tpr.topology.urey_bradley_pairs.is_empty());
+    }
+
+    #[test]
+    fn cmap_terms() {
+        // the SETTLE of each water molecule has been replaced with a CMAP term
+        let tpr = TprFile::parse("tests/test_files/water_2021_cmap.tpr").unwrap();
+        assert_eq!(
+            tpr.topology.cmap_terms,
+            vec![[0, 1, 2, 1, 0], [3, 4, 5, 4, 3], [6, 7, 8, 7, 6]]
+        );
+        assert!(tpr.topology.bonds.is_empty());
+
+        let tpr = TprFile::parse("tests/test_files/small_aa_2021.tpr").unwrap();
+        assert!(tpr.topology.cmap_terms.is_empty());
+    }
+
+    #[test]
+    fn restraints() {
+        // the SETTLE of each water molecule has been replaced with a dihedral restraint
+        let tpr = TprFile::parse("tests/test_files/water_2021_dihres.tpr").unwrap();
+        assert_eq!(tpr.topology.restraints.len(), 3);
+        for (i, restraint) in tpr.topology.restraints.iter().enumerate() {
+            assert_eq!(restraint.kind, RestraintKind::Dihedral);
+            assert_eq!(
+                restraint.atoms,
+                vec![3 * i, 3 * i + 1, 3 * i + 2, 3 * i + 1]
+            );
+        }
+        assert!(tpr.topology.bonds.is_empty());
+
+        let tpr = TprFile::parse("tests/test_files/small_aa_2021.tpr").unwrap();
+        assert!(tpr.topology.restraints.is_empty());
+    }
+
+    #[test]
+    fn parser_default() {
+        let expected = TprFile::parse("tests/test_files/small_aa_2021.tpr").unwrap();
+        let tpr = TprParser::new()
+            .parse("tests/test_files/small_aa_2021.tpr")
+            .unwrap();
+
+        for (a, e) in tpr
+            .topology
+            .atoms
+            .iter()
+            .zip(expected.topology.atoms.iter())
+        {
+            test_eq_atom(a, e);
+        }
+        assert_eq!(tpr.topology.bonds, expected.topology.bonds);
+        assert!(tpr.symbol_table.is_none());
+    }
+
+    #[test]
+    fn parser_no_coordinates() {
+        let tpr = TprParser::new()
+            .coordinates(false)
+            .parse("tests/test_files/water_2021.tpr")
+            .unwrap();
+
+        assert!(tpr.header.has_positions);
+        assert_eq!(tpr.topology.atoms.len(), 9);
+        for atom in tpr.topology.atoms.iter() {
+            assert!(atom.position.is_none());
+            assert!(atom.velocity.is_none());
+            assert!(atom.force.is_none());
+        }
+    }
+
+    #[test]
+    fn parser_no_bstate() {
+        let tpr = TprParser::new()
+            .read_bstate(false)
+            .parse("tests/test_files/water_2021_bstate.tpr")
+            .unwrap();
+
+        assert!(tpr
+            .topology
+            .atoms
+            .iter()
+            .all(|atom| atom.mass_b.is_none() && atom.charge_b.is_none()));
+    }
+
+    #[test]
+    fn parser_fill_missing_elements() {
+        let tpr = TprParser::new()
+            .fill_missing_elements(true)
+            .parse("tests/test_files/water_2021_zero_atomic_number.tpr")
+            .unwrap();
+
+        let expected = [Element::O, Element::H, Element::H];
+        for (atom, element) in tpr.topology.atoms.iter().zip(expected.iter().cycle()) {
+            assert_eq!(atom.element, Some(*element));
+        }
+    }
+
+    #[test]
+    fn parser_options() {
+        let tpr = TprParser::new()
+            .deduplicate_bonds(true)
+            .keep_symbol_table(true)
+            .allow_untested_version(true)
+            .parse("tests/test_files/small_aa_2021_intermolecular.tpr")
+            .unwrap();
+
+        let options = ParseOptions {
+            deduplicate_bonds: true,
+            ..Default::default()
+        };
+        let expected = TprFile::parse_with_options(
+            "tests/test_files/small_aa_2021_intermolecular.tpr",
+            &options,
+        )
+        .unwrap();
+
+        assert_eq!(tpr.topology.bonds, expected.topology.bonds);
+        assert!(tpr.symbol_table.is_some());
+
+        let parser = TprParser::from(options);
+        assert!(parser.options.deduplicate_bonds);
+    }
+
+    #[test]
+    fn parser_progress() {
+        let mut stages = Vec::new();
+        let tpr = TprParser::new()
+            .parse_with_progress("tests/test_files/small_aa_2021.tpr", |stage| {
+                stages.push(stage)
+            })
+            .unwrap();
+
+        assert_eq!(stages.len(), 4 + tpr.molecule_blocks.len());
+        assert_eq!(
+            stages[..3],
+            [
+                ParseStage::HeaderDone,
+                ParseStage::SymtabDone,
+                ParseStage::FFParamsDone
+            ]
+        );
+        assert_eq!(stages.last(), Some(&ParseStage::CoordinatesDone));
+
+        let mut previous = 0;
+        for stage in &stages[3..stages.len() - 1] {
+            match stage {
+                ParseStage::TopologyDone(n_atoms) => {
+                    assert!(*n_atoms > previous);
+                    previous = *n_atoms;
+                }
+                _ => panic!("Unexpected parse stage {:?}.", stage),
+            }
+        }
+        assert_eq!(previous, 182);
+
+        let mut stages = Vec::new();
+        TprParser::new()
+            .coordinates(false)
+            .parse_with_progress("tests/test_files/water_2021.tpr", |stage| {
+                stages.push(stage)
+            })
+            .unwrap();
+
+        assert_eq!(
+            stages,
+            vec![
+                ParseStage::HeaderDone,
+                ParseStage::SymtabDone,
+                ParseStage::FFParamsDone,
+                ParseStage::TopologyDone(9),
+            ]
+        );
+    }
+
+    #[test]
+    fn interaction_histogram() {
+        let tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+        let histogram = tpr.interaction_histogram();
+        assert_eq!(histogram.len(), 1);
+        assert_eq!(histogram.get("F_SETTLE"), Some(&3));
+
+        let tpr = TprFile::parse("tests/test_files/small_cg_2021.tpr").unwrap();
+        let histogram = tpr.interaction_histogram();
+        assert_eq!(histogram.len(), 4);
+        assert_eq!(histogram.get("F_BONDS"), Some(&43));
+        assert_eq!(histogram.get("F_CONSTR"), Some(&20));
+        assert_eq!(histogram.get("F_G96ANGLES"), Some(&56));
+        assert_eq!(histogram.get("F_PDIHS"), Some(&18));
+
+        // intermolecular interactions are included
+        for (file, restraints) in [
+            ("tests/test_files/small_aa_2021.tpr", None),
+            (
+                "tests/test_files/small_aa_2021_intermolecular.tpr",
+                Some(&3),
+            ),
+        ] {
+            let tpr = TprFile::parse(file).unwrap();
+            let histogram = tpr.interaction_histogram();
+            assert_eq!(histogram.get("F_CONSTR"), Some(&176));
+            assert_eq!(histogram.get("F_SETTLE"), Some(&1));
+            assert_eq!(histogram.get("F_UREY_BRADLEY"), Some(&256));
+            assert_eq!(histogram.get("F_PDIHS"), Some(&656));
+            assert_eq!(histogram.get("F_RESTRBONDS"), restraints);
+        }
+    }
+
+    #[test]
+    fn interaction_type_names() {
+        assert_eq!(InteractionType::F_BONDS.as_str(), "F_BONDS");
+        assert_eq!(InteractionType::F_SETTLE.to_string(), "F_SETTLE");
+        assert_eq!(
+            format!("{}", InteractionType::F_UREY_BRADLEY),
+            "F_UREY_BRADLEY"
+        );
+    }
+
+    #[test]
+    fn molecule_type_interactions() {
+        let tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+        assert_eq!(tpr.molecule_types.len(), 1);
+        assert_eq!(tpr.molecule_types[0].name, "TIP3");
+        assert_eq!(
+            tpr.molecule_types[0].interactions,
+            vec![Interaction {
+                interaction_type: InteractionType::F_SETTLE,
+                interacting_atom_indices: AtomIndices::try_from([0, 1, 2].as_slice()).unwrap(),
+            }]
+        );
+        assert!(tpr.intermolecular_interactions.is_empty());
+
+        let tpr = TprFile::parse("tests/test_files/small_cg_2021.tpr").unwrap();
+        let interactions = &tpr.molecule_types[0].interactions;
+        assert_eq!(interactions[0].interaction_type, InteractionType::F_BONDS);
+        assert_eq!(interactions[0].interacting_atom_indices, vec![0, 1]);
+        for interaction in tpr.molecule_types.iter().flat_map(|m| &m.interactions) {
+            assert_eq!(
+                interaction.interacting_atom_indices.len(),
+                match interaction.interaction_type {
+                    InteractionType::F_BONDS | InteractionType::F_CONSTR => 2,
+                    InteractionType::F_G96ANGLES => 3,
+                    InteractionType::F_PDIHS => 4,
+                    _ => panic!("Unexpected interaction type."),
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn intermolecular_interactions() {
+        let tpr = TprFile::parse("tests/test_files/small_aa_2021_intermolecular.tpr").unwrap();
+        assert_eq!(tpr.intermolecular_interactions.len(), 3);
+        for interaction in tpr.intermolecular_interactions.iter() {
+            assert_eq!(interaction.interaction_type, InteractionType::F_RESTRBONDS);
+            let [atom1, atom2] = interaction.interacting_atom_indices[..] else {
+                panic!("Restraint bond must involve two atoms.")
+            };
+            assert!(tpr.topology.bonds.contains(&Bond::new(atom1, atom2)));
+        }
+    }
+
+    #[test]
+    fn molecule_types() {
+        let tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+        let moltypes = tpr.molecule_types();
+        assert_eq!(moltypes.len(), 1);
+        assert_eq!(moltypes[0].name, "TIP3");
+        assert_eq!(moltypes[0].residues.len(), 1);
+        assert_eq!(moltypes[0].residues[0].name, "TIP3");
+        assert_eq!(moltypes[0].residues[0].insertion_code, None);
+
+        let names = moltypes[0]
+            .atoms
+            .iter()
+            .map(|atom| atom.name.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(names, vec!["OH2", "H1", "H2"]);
+        assert_eq!(moltypes[0].atoms[0].element, Some(Element::O));
+        assert!(moltypes[0].atoms.iter().all(|atom| atom.residue_index == 0));
+
+        assert_eq!(tpr.molecule_blocks().len(), 1);
+        assert_eq!(tpr.molecule_blocks()[0].n_molecules, 3);
+    }
+
+    #[test]
+    fn molecule_types_match_topology() {
+        for file in [
+            "tests/test_files/small_aa_2021.tpr",
+            "tests/test_files/small_cg_2021.tpr",
+            "tests/test_files/large_2021.tpr",
+        ] {
+            let tpr = TprFile::parse(file).unwrap();
+
+            let mut atoms = tpr.topology.atoms.iter();
+            for block in tpr.molecule_blocks() {
+                let moltype = &tpr.molecule_types()[block.molecule_type];
+                assert_eq!(moltype.name, block.name);
+
+                for _ in 0..block.n_molecules {
+                    for moltype_atom in moltype.atoms.iter() {
+                        let atom = atoms.next().unwrap();
+                        let residue = &moltype.residues[moltype_atom.residue_index];
+                        assert_eq!(atom.atom_name, moltype_atom.name);
+                        assert_eq!(atom.residue_name, residue.name);
+                        assert_eq!(atom.original_residue_number, residue.number);
+                        assert_eq!(atom.mass, moltype_atom.mass);
+                        assert_eq!(atom.charge, moltype_atom.charge);
+                        assert_eq!(atom.element, moltype_atom.element);
+                    }
+                }
+            }
+
+            assert!(atoms.next().is_none());
+        }
+    }
+
+    /// Find all pairs of atoms closer than `cutoff` by checking every pair.
+    fn brute_force_pairs(tpr: &TprFile, cutoff: f64, simbox: Option<&SimBox>) -> Vec<Bond> {
+        let positions = tpr.topology.positions().unwrap();
+        let mut pairs = Vec::new();
+        for i in 0..positions.len() {
+            for j in (i + 1)..positions.len() {
+                let dx = match simbox {
+                    Some(simbox) => simbox.min_image(positions[i], positions[j]),
+                    None => std::array::from_fn(|d| positions[j][d] - positions[i][d]),
+                };
+                if dx.iter().map(|x| x * x).sum::<f64>().sqrt() <= cutoff {
+                    pairs.push(Bond::new(i, j));
+                }
+            }
+        }
+
+        pairs
+    }
+
+    #[test]
+    fn infer_bonds_by_distance() {
+        for file in [
+            "tests/test_files/small_aa_2021.tpr",
+            "tests/test_files/triclinic_2021.tpr",
+        ] {
+            let mut tpr = TprFile::parse(file).unwrap();
+            let simbox = tpr.simbox.clone().unwrap();
+
+            for (cutoff, simbox) in [
+                (0.2, None),
+                (0.2, Some(&simbox)),
+                (0.8, None),
+                (0.8, Some(&simbox)),
+            ] {
+                tpr.topology.bonds.clear();
+                tpr.topology.infer_bonds_by_distance(cutoff, simbox);
+                assert_eq!(tpr.topology.bonds, brute_force_pairs(&tpr, cutoff, simbox));
+            }
+        }
+    }
+
+    #[test]
+    fn infer_bonds_by_distance_existing() {
+        let mut tpr = TprFile::parse("tests/test_files/small_aa_2021.tpr").unwrap();
+        let simbox = tpr.simbox.clone().unwrap();
+        let original = tpr.topology.bonds.clone();
+
+        tpr.topology.infer_bonds_by_distance(0.2, Some(&simbox));
+
+        // original bonds are kept in place and not duplicated
+        assert_eq!(tpr.topology.bonds[..original.len()], original[..]);
+        let mut expected = brute_force_pairs(&tpr, 0.2, Some(&simbox));
+        expected.retain(|bond| !original.contains(bond));
+        assert_eq!(tpr.topology.bonds[original.len()..], expected[..]);
+
+        // atoms without positions are skipped
+        tpr.topology.bonds.clear();
+        for atom in tpr.topology.atoms.iter_mut().skip(1) {
+            atom.position = None;
+        }
+        tpr.topology.infer_bonds_by_distance(100.0, None);
+        assert!(tpr.topology.bonds.is_empty());
+    }
+
+    #[test]
+    fn is_virtual_site() {
+        let mut tpr = TprFile::parse("tests/test_files/small_aa_2021.tpr").unwrap();
+        assert!(tpr
+            .topology
+            .atoms
+            .iter()
+            .all(|atom| !atom.is_virtual_site()));
+
+        tpr.topology.atoms[5].mass = 0.0;
+        assert!(tpr.topology.atoms[5].is_virtual_site());
+        assert!(!tpr.topology.atoms[4].is_virtual_site());
+    }
+
+    #[test]
+    fn molecule_type_weights() {
+        let tpr = TprFile::parse("tests/test_files/small_aa_2021.tpr").unwrap();
+        let weights = tpr.molecule_type_weights();
+
+        let expected = [
+            ("Protein", 260.358),
+            ("POPC", 760.094),
+            ("SOL", 18.016),
+            ("CL", 35.45),
+        ];
+        assert_eq!(weights.len(), expected.len());
+        for ((name, weight), (exp_name, exp_weight)) in weights.iter().zip(expected) {
+            assert_eq!(name, exp_name);
+            assert_approx_eq!(f64, *weight, exp_weight, epsilon = 0.001);
+        }
+
+        assert_approx_eq!(
+            f64,
+            tpr.molecule_types()[1].molecular_weight(),
+            760.094,
+            epsilon = 0.001
+        );
+    }
+
+    #[test]
+    fn chain_ids() {
+        let tpr = TprFile::parse("tests/test_files/small_aa_2021.tpr").unwrap();
+        for atom in tpr.topology.atoms.iter() {
+            if atom.molecule_id == 0 {
+                assert_eq!(atom.chain_id, 'A');
+            } else {
+                assert_eq!(atom.chain_id, ' ');
+            }
+        }
+
+        let tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+        assert!(tpr.topology.atoms.iter().all(|atom| atom.chain_id == ' '));
+    }
+
+    #[test]
+    fn trim_names() {
+        // residue name changed to `TIP ` and name of the oxygen atom changed to ` O `
+        let tpr = TprFile::parse("tests/test_files/water_2021_padded_names.tpr").unwrap();
+        assert_eq!(tpr.topology.atoms[0].atom_name, " O ");
+        assert_eq!(tpr.topology.atoms[0].residue_name, "TIP ");
+        assert_eq!(tpr.molecule_blocks[0].name, "TIP ");
+
+        let tpr = TprParser::new()
+            .trim_names(true)
+            .parse("tests/test_files/water_2021_padded_names.tpr")
+            .unwrap();
+        for (atom, name) in tpr
+            .topology
+            .atoms
+            .iter()
+            .zip(["O", "H1", "H2"].iter().cycle())
+        {
+            assert_eq!(atom.atom_name, *name);
+            assert_eq!(atom.residue_name, "TIP");
+        }
+        assert_eq!(tpr.molecule_blocks[0].name, "TIP");
+        assert_eq!(tpr.molecule_types[0].name, "TIP");
+    }
+
+    #[test]
+    fn wrapped_residue_numbers() {
+        // both residues of the protein have the original residue number 1
+        let tpr = TprFile::parse("tests/test_files/small_aa_2021_wrapped_resnums.tpr").unwrap();
+        let expected = TprFile::parse("tests/test_files/small_aa_2021.tpr").unwrap();
+
+        assert_eq!(tpr.topology.atoms.len(), expected.topology.atoms.len());
+        for (atom, exp) in tpr
+            .topology
+            .atoms
+            .iter()
+            .zip(expected.topology.atoms.iter())
+        {
+            assert_eq!(atom.residue_number, exp.residue_number);
+            if exp.residue_name == "LYS" {
+                assert_eq!(atom.original_residue_number, 1);
+                assert_eq!(atom.residue_number, 2);
+            } else {
+                assert_eq!(atom.original_residue_number, exp.original_residue_number);
+            }
+        }
+    }
+
+    #[test]
+    fn n_molecule_types_blocks() {
+        let tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+        assert_eq!(tpr.n_molecule_types(), 1);
+        assert_eq!(tpr.n_molecule_blocks(), 1);
+
+        for (file, expected) in [
+            ("tests/test_files/small_aa_2021.tpr", 4),
+            ("tests/test_files/small_cg_2021.tpr", 4),
+            ("tests/test_files/large_2021.tpr", 6),
+        ] {
+            let tpr = TprFile::parse(file).unwrap();
+            assert_eq!(tpr.n_molecule_types(), expected);
+            assert_eq!(tpr.n_molecule_blocks(), expected);
+        }
+    }
+
+    #[test]
+    fn atom_by_number() {
+        let tpr = TprFile::parse("tests/test_files/small_aa_2021.tpr").unwrap();
+        let topology = &tpr.topology;
+
+        for atom in topology.atoms.iter() {
+            assert!(std::ptr::eq(
+                topology.atom_by_number(atom.atom_number).unwrap(),
+                atom
+            ));
+        }
+
+        assert_eq!(topology.atom_by_number(1).unwrap().atom_name, "N");
+        assert_eq!(topology.atom_by_number(182).unwrap().atom_number, 182);
+        assert!(topology.atom_by_number(0).is_none());
+        assert!(topology.atom_by_number(-1).is_none());
+        assert!(topology.atom_by_number(183).is_none());
+        assert!(topology.atom_by_number(i32::MIN).is_none());
+    }
+
+    #[test]
+    fn select_by_name() {
+        let tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+        let topology = &tpr.topology;
+
+        assert_eq!(
+            topology.atoms_in_residue_name("TIP3"),
+            (0..9).collect::<Vec<usize>>()
+        );
+        assert_eq!(topology.atoms_with_name("H1"), vec![1, 4, 7]);
+        assert_eq!(topology.atoms_with_name("OH2"), vec![0, 3, 6]);
+        assert!(topology.atoms_in_residue_name("SOL").is_empty());
+        assert!(topology.atoms_with_name("oh2").is_empty());
+
+        let tpr = TprFile::parse("tests/test_files/small_aa_2021.tpr").unwrap();
+        let topology = &tpr.topology;
+
+        assert_eq!(
+            topology.atoms_in_residue_name("POPC"),
+            (44..178).collect::<Vec<usize>>()
+        );
+
+        assert_eq!(
+            topology.atoms_with_name("CL"),
+            topology.atoms_in_residue_name("CL")
+        );
+    }
+
+    #[test]
+    fn subset_strip_water() {
+        let tpr = TprFile::parse("tests/test_files/small_aa_2021.tpr").unwrap();
+        let topology = &tpr.topology;
+
+        let selected = (0..topology.atoms.len())
+            .filter(|&index| topology.atoms[index].residue_name != "SOL")
+            .collect::<Vec<usize>>();
+        let stripped = topology.subset(&selected);
+
+        assert_eq!(stripped.atoms.len(), selected.len());
+        for (i, (atom, &original)) in stripped.atoms.iter().zip(selected.iter()).enumerate() {
+            let original = &topology.atoms[original];
+            assert_eq!(atom.atom_number, i as i32 + 1);
+            assert_eq!(atom.atom_name, original.atom_name);
+            assert_eq!(atom.residue_name, original.residue_name);
+            assert_eq!(atom.residue_number, original.residue_number);
+            assert_eq!(atom.position, original.position);
+        }
+
+        let expected = topology
+            .bonds
+            .iter()
+            .filter(|bond| {
+                topology.atoms[bond.atom1].residue_name != "SOL"
+                    && topology.atoms[bond.atom2].residue_name != "SOL"
+            })
+            .map(|bond| {
+                let remap = |index: usize| selected.binary_search(&index).unwrap();
+                Bond::new(remap(bond.atom1), remap(bond.atom2))
+            })
+            .collect::<Vec<Bond>>();
+        assert_eq!(stripped.bonds, expected);
+        assert!(stripped.bonds.len() < topology.bonds.len());
+        assert_eq!(stripped.urey_bradley_pairs.len(), 256);
+
+        // round trip
+        let all = (0..topology.atoms.len()).collect::<Vec<usize>>();
+        let copy = topology.subset(&all);
+        assert_eq!(copy.atoms.len(), topology.atoms.len());
+        for (atom, original) in copy.atoms.iter().zip(topology.atoms.iter()) {
+            assert_eq!(atom.atom_number, original.atom_number);
+            assert_eq!(atom.atom_name, original.atom_name);
+        }
+        assert_eq!(copy.bonds, topology.bonds);
+        assert_eq!(copy.urey_bradley_pairs, topology.urey_bradley_pairs);
+    }
+
+    #[test]
+    fn subset_remap() {
+        let tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+        let subset = tpr.topology.subset(&[5, 3, 4, 3, 0]);
+        let names = subset
+            .atoms
+            .iter()
+            .map(|atom| atom.atom_name.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(names, vec!["H2", "OH2", "H1", "OH2"]);
+        assert_eq!(subset.bonds, vec![Bond::new(1, 2), Bond::new(1, 0)]);
+
+        let tpr = TprFile::parse("tests/test_files/water_2021_cmap.tpr").unwrap();
+        let subset = tpr.topology.subset(&[8, 7, 6, 3, 4]);
+        assert_eq!(subset.cmap_terms, vec![[2, 1, 0, 1, 2]]);
+
+        let tpr = TprFile::parse("tests/test_files/water_2021_dihres.tpr").unwrap();
+        let subset = tpr.topology.subset(&[8, 7, 6, 3, 4]);
+        assert_eq!(subset.restraints.len(), 1);
+        assert_eq!(subset.restraints[0].kind, RestraintKind::Dihedral);
+        assert_eq!(subset.restraints[0].atoms, vec![2, 1, 0, 1]);
+    }
+
+    #[test]
+    fn merge() {
+        let mut merged = TprFile::parse("tests/test_files/water_2021.tpr")
+            .unwrap()
+            .topology;
+        let other = TprFile::parse("tests/test_files/small_aa_2021.tpr")
+            .unwrap()
+            .topology;
+        merged.merge(&other);
+
+        assert_eq!(merged.atoms.len(), 9 + other.atoms.len());
+        for (i, atom) in merged.atoms.iter().enumerate() {
+            assert_eq!(atom.atom_number, i as i32 + 1);
+        }
+
+        for (atom, original) in merged.atoms[9..].iter().zip(other.atoms.iter()) {
+            assert_eq!(atom.atom_name, original.atom_name);
+            assert_eq!(atom.residue_number, original.residue_number + 3);
+            assert_eq!(
+                atom.original_residue_number,
+                original.original_residue_number
+            );
+            assert_eq!(atom.molecule_id, original.molecule_id + 3);
+            assert_eq!(atom.chain_id, original.chain_id);
+            assert_eq!(atom.position, original.position);
+        }
+
+        assert_eq!(merged.bonds.len(), 6 + other.bonds.len());
+        for (bond, original) in merged.bonds[6..].iter().zip(other.bonds.iter()) {
+            assert_eq!(bond.atom1, original.atom1 + 9);
+            assert_eq!(bond.atom2, original.atom2 + 9);
+        }
+        assert_eq!(merged.urey_bradley_pairs.len(), 256);
+        assert_eq!(merged.urey_bradley_pairs[0], Bond::new(54, 55));
+
+        // merging into an empty topology
+        let mut merged = other.subset(&[]);
+        merged.merge(&other);
+        assert_eq!(merged.atoms.len(), other.atoms.len());
+        assert_eq!(merged.bonds, other.bonds);
+        for (atom, original) in merged.atoms.iter().zip(other.atoms.iter()) {
+            assert_eq!(atom.atom_number, original.atom_number);
+            assert_eq!(atom.residue_number, original.residue_number);
+            assert_eq!(atom.molecule_id, original.molecule_id);
+        }
+    }
+
+    #[test]
+    fn merge_cmap_restraints() {
+        let mut merged = TprFile::parse("tests/test_files/water_2021_cmap.tpr")
+            .unwrap()
+            .topology;
+        let other = TprFile::parse("tests/test_files/water_2021_dihres.tpr")
+            .unwrap()
+            .topology;
+        merged.merge(&other);
+
+        assert_eq!(merged.atoms.len(), 18);
+        assert_eq!(merged.cmap_terms.len(), 3);
+        assert_eq!(merged.restraints.len(), 3);
+        assert_eq!(merged.restraints[0].atoms, vec![9, 10, 11, 10]);
+    }
+
+    #[test]
+    fn topology_diff_identical() {
+        for file in [
+            "tests/test_files/water_2021.tpr",
+            "tests/test_files/small_aa_2021.tpr",
+        ] {
+            let tpr = TprFile::parse(file).unwrap();
+            let diff = tpr.topology_diff(&TprFile::parse(file).unwrap());
+            assert!(diff.is_empty());
+        }
+    }
+
+    #[test]
+    fn topology_diff_atoms() {
+        let tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+
+        // atom and residue names differ
+        let padded = TprFile::parse("tests/test_files/water_2021_padded_names.tpr").unwrap();
+        let diff = tpr.topology_diff(&padded);
+        assert_eq!(diff.changed_atoms, (0..9).collect::<Vec<usize>>());
+        assert!(diff.added_atoms.is_empty());
+        assert!(diff.removed_atoms.is_empty());
+        assert!(diff.added_bonds.is_empty());
+        assert!(diff.removed_bonds.is_empty());
+
+        // positions within the tolerance
+        let mut shifted = tpr.clone();
+        shifted.topology.translate([1e-6, 0.0, -1e-6]);
+        assert!(tpr.topology_diff(&shifted).is_empty());
+
+        // positions outside of the tolerance
+        let mut shifted = tpr.clone();
+        shifted.topology.translate([0.0, 0.01, 0.0]);
+        assert_eq!(
+            tpr.topology_diff(&shifted).changed_atoms,
+            (0..9).collect::<Vec<usize>>()
+        );
+
+        // missing positions
+        let mut missing = tpr.clone();
+        missing.topology.atoms[4].position = None;
+        assert_eq!(tpr.topology_diff(&missing).changed_atoms, vec![4]);
+
+        // changed charge
+        let mut charged = tpr.clone();
+        charged.topology.atoms[7].charge += 0.1;
+        assert_eq!(tpr.topology_diff(&charged).changed_atoms, vec![7]);
+    }
+
+    #[test]
+    fn topology_diff_added_removed() {
+        let tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+
+        let mut smaller = tpr.clone();
+        smaller.topology = tpr.topology.subset(&[0, 1, 2, 3, 4, 5]);
+
+        let diff = tpr.topology_diff(&smaller);
+        assert_eq!(diff.removed_atoms, vec![6, 7, 8]);
+        assert!(diff.added_atoms.is_empty());
+        assert!(diff.changed_atoms.is_empty());
+        assert_eq!(diff.removed_bonds, vec![Bond::new(6, 7), Bond::new(6, 8)]);
+        assert!(diff.added_bonds.is_empty());
+
+        let diff = smaller.topology_diff(&tpr);
+        assert_eq!(diff.added_atoms, vec![6, 7, 8]);
+        assert!(diff.removed_atoms.is_empty());
+        assert_eq!(diff.added_bonds, vec![Bond::new(6, 7), Bond::new(6, 8)]);
+        assert!(diff.removed_bonds.is_empty());
+
+        // bond order and orientation does not matter
+        let mut reversed = tpr.clone();
+        reversed.topology.bonds.reverse();
+        for bond in reversed.topology.bonds.iter_mut() {
+            std::mem::swap(&mut bond.atom1, &mut bond.atom2);
+        }
+        assert!(tpr.topology_diff(&reversed).is_empty());
+    }
+
+    #[test]
+    fn legacy_version() {
+        // synthetic file: small_aa_5.tpr (version 103) manually converted to version 102
+        // by changing the version and removing the intermolecular interactions flag;
+        // parsing of tpr files actually written by Gromacs 5.0 is not tested
+        match TprFile::parse("tests/test_files/small_aa_5_legacy.tpr") {
+            Err(ParseTprError::UnsupportedVersion(102)) => (),
+            _ => panic!("Legacy tpr file should not be parsed by default."),
+        }
+
+        let legacy = TprParser::new()
+            .allow_legacy(true)
+            .parse("tests/test_files/small_aa_5_legacy.tpr")
+            .unwrap();
+        let expected = TprFile::parse("tests/test_files/small_aa_5.tpr").unwrap();
+
+        assert_eq!(legacy.header.tpr_version, 102);
+        assert_eq!(legacy.system_name, expected.system_name);
+        assert!(expected.topology_diff(&legacy).is_empty());
+        assert_eq!(legacy.topology.bonds, expected.topology.bonds);
+        assert!(legacy.intermolecular_interactions.is_empty());
+
+        match TprParser::new()
+            .allow_legacy(true)
+            .parse("tests/test_files/small_aa_5_version_99.tpr")
+        {
+            Err(ParseTprError::UnsupportedVersion(99)) => (),
+            _ => panic!("Tpr file version 99 should not be parsed."),
+        }
+    }
+
+    #[test]
+    fn error_clone_eq() {
+        let result: TprResult<TprFile> =
+            TprFile::parse("tests/test_files/water_2021_truncated.tpr");
+        let error = result.unwrap_err();
+        let cloned = error.clone();
+        assert_eq!(error, cloned);
+        assert_eq!(error.to_string(), cloned.to_string());
+
+        let other = TprFile::parse("tests/test_files/empty.tpr").unwrap_err();
+        assert_ne!(error, other);
+
+        let io_error = std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "end of file");
+        assert_eq!(
+            ParseTprError::from(io_error),
+            ParseTprError::CouldNotRead {
+                kind: std::io::ErrorKind::UnexpectedEof,
+                message: String::from("end of file"),
+            }
+        );
+    }
+
+    #[test]
+    fn atoms_bonds_iterators() {
+        let tpr = TprFile::parse("tests/test_files/small_aa_2021.tpr").unwrap();
+
+        assert_eq!(tpr.atoms().count(), 182);
+        for (atom, expected) in tpr.atoms().zip(tpr.topology.atoms.iter()) {
+            assert!(std::ptr::eq(atom, expected));
+        }
+
+        assert_eq!(tpr.bonds().count(), 178);
+        assert!(tpr.bonds().eq(tpr.topology.bonds.iter()));
+    }
+
+    #[test]
+    fn settle_include_hh() {
+        let tpr = TprParser::new()
+            .settle_include_hh(true)
+            .parse("tests/test_files/water_2021.tpr")
+            .unwrap();
+
+        let expected = (0..3)
+            .flat_map(|i| {
+                let o = 3 * i;
+                [
+                    Bond::new(o, o + 1),
+                    Bond::new(o, o + 2),
+                    Bond::new(o + 1, o + 2),
+                ]
+            })
+            .collect::<Vec<Bond>>();
+        assert_eq!(tpr.topology.bonds, expected);
+
+        let tpr = TprParser::new()
+            .settle_include_hh(true)
+            .parse("tests/test_files/small_aa_2021.tpr")
+            .unwrap();
+        assert_eq!(tpr.topology.bonds.len(), 179);
+    }
+
+    #[test]
+    fn water_atom_indices() {
+        let tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+        assert_eq!(
+            tpr.topology.water_atom_indices(),
+            (0..9).collect::<Vec<usize>>()
+        );
+        assert_eq!(
+            tpr.topology.water_atom_indices_with(&["tip3"]),
+            (0..9).collect::<Vec<usize>>()
+        );
+        assert!(tpr.topology.water_atom_indices_with(&["SOL"]).is_empty());
+
+        let tpr = TprFile::parse("tests/test_files/small_aa_2021.tpr").unwrap();
+        let water = tpr.topology.water_atom_indices();
+        assert_eq!(water, tpr.topology.atoms_in_residue_name("SOL"));
+        assert!(!water.is_empty());
+
+        let tpr = TprFile::parse("tests/test_files/small_cg_2021.tpr").unwrap();
+        let water = tpr.topology.water_atom_indices();
+        assert_eq!(water, tpr.topology.atoms_in_residue_name("W"));
+        assert!(!water.is_empty());
+        assert!(tpr.topology.water_atom_indices_with(&[]).is_empty());
+    }
+
+    #[test]
+    fn coordinates_f32() {
+        let to_f32 = |vectors: Option<Vec<[f64; 3]>>| {
+            vectors.map(|v| {
+                v.into_iter()
+                    .map(|x| x.map(|y| y as f32))
+                    .collect::<Vec<[f32; 3]>>()
+            })
+        };
+
+        for file in [
+            "tests/test_files/small_aa_2021.tpr",
+            "tests/test_files/small_cg_5.tpr",
+            "tests/test_files/double_2023.tpr",
+            "tests/test_files/water_2021_no_coordinates.tpr",
+        ] {
+            let topology = TprFile::parse(file).unwrap().topology;
+            assert_eq!(topology.positions_f32(), to_f32(topology.positions()));
+            assert_eq!(topology.velocities_f32(), to_f32(topology.velocities()));
+            assert_eq!(topology.forces_f32(), to_f32(topology.forces()));
+        }
+
+        // conversion is lossless for single-precision files
+        let topology = TprFile::parse("tests/test_files/small_aa_2021.tpr")
+            .unwrap()
+            .topology;
+        let positions = topology.positions_f32().unwrap();
+        assert_eq!(positions.len(), 182);
+        for (position, atom) in positions.iter().zip(topology.atoms.iter()) {
+            assert_eq!(position.map(f64::from), atom.position.unwrap());
+        }
+
+        let topology = TprFile::parse("tests/test_files/water_2021_no_coordinates.tpr")
+            .unwrap()
+            .topology;
+        assert!(topology.positions_f32().is_none());
+    }
+
+    #[test]
+    fn atoms_per_molecule() {
+        let tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+        assert_eq!(tpr.molecule_blocks[0].atoms_per_molecule, 3);
+
+        for file in [
+            "tests/test_files/small_aa_2021.tpr",
+            "tests/test_files/small_cg_5.tpr",
+            "tests/test_files/large_2021.tpr",
+        ] {
+            let tpr = TprFile::parse(file).unwrap();
+            for block in tpr.molecule_blocks() {
+                assert_eq!(
+                    block.atoms_per_molecule,
+                    tpr.molecule_types()[block.molecule_type].atoms.len()
+                );
+            }
+        }
+
+        let tpr = TprFile::parse("tests/test_files/small_aa_2021.tpr").unwrap();
+        let atoms = tpr
+            .molecule_blocks()
+            .iter()
+            .map(|block| block.atoms_per_molecule)
+            .collect::<Vec<usize>>();
+        assert_eq!(atoms, vec![44, 134, 3, 1]);
+    }
+
+    #[test]
+    fn posres_counts() {
+        let counts = |file: &str| {
+            TprFile::parse(file)
+                .unwrap()
+                .molecule_blocks()
+                .iter()
+                .map(|block| (block.posres_count, block.fbposres_count))
+                .collect::<Vec<(usize, usize)>>()
+        };
+
+        assert_eq!(
+            counts("tests/test_files/large_2021_aa_posres.tpr"),
+            vec![(17152, 17152), (0, 0), (0, 0), (0, 0)]
+        );
+        assert_eq!(
+            counts("tests/test_files/large_5_posres.tpr"),
+            vec![(1298, 1298), (0, 0), (19809, 19809), (0, 0), (0, 0)]
+        );
+
+        for file in [
+            "tests/test_files/large_2021_aa.tpr",
+            "tests/test_files/small_aa_2021.tpr",
+        ] {
+            assert!(counts(file).iter().all(|&count| count == (0, 0)));
+        }
+    }
+
+    #[test]
+    fn parse_many() {
+        let paths = [
+            "tests/test_files/water_2021.tpr",
+            "tests/test_files/nonexistent.tpr",
+            "tests/test_files/small_aa_2021.tpr",
+            "tests/test_files/water_2021_truncated.tpr",
+            "tests/test_files/small_cg_5.tpr",
+            "tests/test_files/empty.tpr",
+            "tests/test_files/large_2021.tpr",
+        ];
+
+        let results = minitpr::parse_many(&paths);
+        assert_eq!(results.len(), paths.len());
+
+        for (path, result) in paths.iter().zip(results.iter()) {
+            match TprFile::parse(path) {
+                Ok(expected) => {
+                    let tpr = result.as_ref().unwrap();
+                    assert_eq!(tpr.system_name, expected.system_name);
+                    assert!(tpr.topology_diff(&expected).is_empty());
+                }
+                Err(expected) => assert_eq!(result.as_ref().unwrap_err(), &expected),
+            }
+        }
+
+        let results = TprParser::new().coordinates(false).parse_many(&paths[..3]);
+        assert_eq!(results.len(), 3);
+        assert!(results[0]
+            .as_ref()
+            .unwrap()
+            .topology
+            .atoms
+            .iter()
+            .all(|atom| atom.position.is_none()));
+
+        assert!(minitpr::parse_many::<&str>(&[]).is_empty());
+    }
+
+    #[test]
+    fn atom_indices() {
+        let indices = AtomIndices::try_from(vec![4, 2, 7]).unwrap();
+        assert_eq!(indices.len(), 3);
+        assert_eq!(&indices[..], &[4, 2, 7]);
+        assert_eq!(indices, [4, 2, 7]);
+        assert_eq!(indices, vec![4, 2, 7]);
+        assert_eq!(indices.iter().sum::<usize>(), 13);
+        assert_eq!(Vec::from(indices), vec![4, 2, 7]);
+
+        assert_eq!(
+            AtomIndices::try_from([0, 1, 2, 3, 4].as_slice()).unwrap(),
+            [0, 1, 2, 3, 4]
+        );
+        assert!(AtomIndices::try_from(Vec::new()).unwrap().is_empty());
+        assert_eq!(
+            AtomIndices::try_from(vec![0; 6]),
+            Err(ParseTprError::TooManyInteractingAtoms(6))
+        );
+    }
+
+    #[test]
+    fn keep_coordinate_blocks() {
+        let tpr = TprFile::parse("tests/test_files/small_aa_2021.tpr").unwrap();
+        assert!(tpr.coordinates.is_none());
+
+        let kept = TprParser::new()
+            .keep_coordinate_blocks(true)
+            .parse("tests/test_files/small_aa_2021.tpr")
+            .unwrap();
+        let coordinates = kept.coordinates.as_ref().unwrap();
+
+        assert_eq!(coordinates.positions, tpr.topology.positions().unwrap());
+        assert_eq!(
+            coordinates.velocities.is_empty(),
+            tpr.topology.velocities().is_none()
+        );
+        assert_eq!(
+            coordinates.forces.is_empty(),
+            tpr.topology.forces().is_none()
+        );
+
+        // coordinates are still assigned to the atoms
+        assert_eq!(kept.topology.positions(), tpr.topology.positions());
+
+        let skipped = TprParser::new()
+            .keep_coordinate_blocks(true)
+            .coordinates(false)
+            .parse("tests/test_files/small_aa_2021.tpr")
+            .unwrap();
+        assert!(skipped.coordinates.is_none());
+    }
+
+    #[test]
+    fn water_2021_nobox() {
+        let tpr = TprFile::parse("tests/test_files/water_2021_nobox.tpr").unwrap();
+        let reference = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+
+        assert!(!tpr.header.has_box);
+        assert!(tpr.simbox.is_none());
+        assert_eq!(tpr.header.n_coupling_groups, 1);
+        assert_eq!(tpr.system_name, reference.system_name);
+
+        assert_eq!(tpr.topology.atoms.len(), 9);
+        assert_eq!(tpr.topology.bonds, reference.topology.bonds);
+        assert_eq!(tpr.topology.positions(), reference.topology.positions());
+        assert_eq!(tpr.topology.velocities(), reference.topology.velocities());
+
+        for (atom, expected) in tpr
+            .topology
+            .atoms
+            .iter()
+            .zip(reference.topology.atoms.iter())
+        {
+            assert_eq!(atom.atom_name, expected.atom_name);
+            assert_eq!(atom.residue_name, expected.residue_name);
+            assert_eq!(atom.mass, expected.mass);
+            assert_eq!(atom.charge, expected.charge);
+        }
+    }
+
+    #[test]
+    fn parse_with_warnings() {
+        let (tpr, warnings) =
+            TprFile::parse_with_warnings("tests/test_files/small_aa_2021.tpr").unwrap();
+        assert_eq!(tpr.topology.atoms.len(), 182);
+        assert!(warnings.is_empty());
+
+        let (_, warnings) =
+            TprFile::parse_with_warnings("tests/test_files/small_cg_2021.tpr").unwrap();
+        assert_eq!(warnings, vec![ParseWarning::UnknownElements(77)]);
+        assert!(warnings[0].to_string().contains("77"));
+
+        let (_, warnings) =
+            TprFile::parse_with_warnings("tests/test_files/water_2021_zero_atomic_number.tpr")
+                .unwrap();
+        assert_eq!(warnings, vec![ParseWarning::UnknownElements(3)]);
+
+        let (_, warnings) = TprParser::new()
+            .fill_missing_elements(true)
+            .parse_with_warnings("tests/test_files/water_2021_zero_atomic_number.tpr")
+            .unwrap();
+        assert!(warnings.is_empty());
+
+        let (_, warnings) = TprParser::new()
+            .allow_legacy(true)
+            .parse_with_warnings("tests/test_files/small_aa_5_legacy.tpr")
+            .unwrap();
+        assert_eq!(warnings, vec![ParseWarning::LegacyVersion(102)]);
+
+        match TprFile::parse_with_warnings("tests/test_files/small_aa_5_legacy.tpr") {
+            Err(ParseTprError::UnsupportedVersion(102)) => (),
+            Err(e) => panic!("Incorrect error type returned: {:?}", e),
+            Ok(_) => panic!("Parsing should have failed, but it succeeded."),
+        }
+    }
+
+    #[test]
+    fn lambda_state() {
+        let mut tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+        assert_eq!(tpr.header.lambda_state(), Some(0));
+        assert_eq!(tpr.header.lambda, 0.0);
+
+        tpr.header.fep_state = 3;
+        assert_eq!(tpr.header.lambda_state(), Some(3));
+
+        tpr.header.fep_state = -1;
+        assert_eq!(tpr.header.lambda_state(), None);
+    }
+
+    #[test]
+    fn parse_bytes() {
+        for file in [
+            "tests/test_files/small_aa_2021.tpr",
+            "tests/test_files/small_cg_5.tpr",
+            "tests/test_files/double_2023.tpr",
+        ] {
+            let bytes = std::fs::read(file).unwrap();
+            let from_file = TprFile::parse(file).unwrap();
+            let from_bytes = TprFile::parse_bytes(&bytes).unwrap();
+
+            assert_eq!(from_file.header, from_bytes.header);
+            assert_eq!(from_file.system_name, from_bytes.system_name);
+            assert_eq!(from_file.topology.bonds, from_bytes.topology.bonds);
+            assert_eq!(
+                from_file.topology.positions(),
+                from_bytes.topology.positions()
+            );
+            assert_eq!(
+                from_file.topology.content_hash(),
+                from_bytes.topology.content_hash()
+            );
+        }
+
+        let bytes = std::fs::read("tests/test_files/water_2021_truncated.tpr").unwrap();
+        match TprParser::new().parse_bytes(&bytes) {
+            Err(ParseTprError::Truncated { .. }) => (),
+            Err(e) => panic!("Incorrect error type returned: {:?}", e),
+            Ok(_) => panic!("Parsing should have failed, but it succeeded."),
+        }
+
+        match TprFile::parse_bytes(&[]) {
+            Err(ParseTprError::EmptyFile) => (),
+            Err(e) => panic!("Incorrect error type returned: {:?}", e),
+            Ok(_) => panic!("Parsing should have failed, but it succeeded."),
+        }
+    }
+
+    #[test]
+    fn colors_enabled() {
+        use minitpr::errors::colors_enabled;
+
+        // automatic detection
+        assert!(colors_enabled(None, true, true));
+        assert!(!colors_enabled(None, true, false));
+        assert!(!colors_enabled(None, false, true));
+        assert!(!colors_enabled(None, false, false));
+
+        // override
+        assert!(colors_enabled(Some(true), true, false));
+        assert!(colors_enabled(Some(true), false, false));
+        assert!(!colors_enabled(Some(false), true, true));
+    }
+
+    #[test]
+    fn error_no_colors_override() {
+        minitpr::errors::set_colors_override(Some(false));
+
+        let error = TprFile::parse("tests/test_files/nonexistent.tpr").unwrap_err();
+        let error_string = error.to_string();
+        let warning_string = ParseWarning::UnknownElements(3).to_string();
+
+        minitpr::errors::set_colors_override(None);
+
+        assert_eq!(
+            error_string,
+            "error: file 'tests/test_files/nonexistent.tpr' could not be opened for reading"
+        );
+        assert_eq!(
+            warning_string,
+            "warning: `3` atom(s) with unrecognized element"
+        );
+    }
+
+    #[test]
+    fn gromacs_major_version() {
+        for (file, expected) in [
+            ("tests/test_files/small_aa_5.tpr", 5),
+            ("tests/test_files/small_aa_2016.tpr", 2016),
+            ("tests/test_files/small_aa_2021.tpr", 2021),
+            ("tests/test_files/double_2023.tpr", 2023),
+        ] {
+            let tpr = TprFile::parse(file).unwrap();
+            assert_eq!(tpr.header.gromacs_major_version(), Some(expected));
+        }
+
+        let mut header = TprFile::parse("tests/test_files/water_2021.tpr")
+            .unwrap()
+            .header;
+
+        header.gromacs_version = String::from("VERSION 2024-dev-20230101-abcdef");
+        assert_eq!(header.gromacs_major_version(), Some(2024));
+
+        header.gromacs_version = String::from("VERSION unknown");
+        assert_eq!(header.gromacs_major_version(), None);
+
+        header.gromacs_version = String::new();
+        assert_eq!(header.gromacs_major_version(), None);
+    }
+
+    #[test]
+    fn precision() {
+        let single = TprFile::parse("tests/test_files/small_aa_2021.tpr").unwrap();
+        assert!(!single.header.is_double());
+        assert_eq!(single.header.precision_bytes(), 4);
+        assert_eq!(single.header.precision.to_string(), "single");
+
+        let double = TprFile::parse("tests/test_files/double_2023.tpr").unwrap();
+        assert!(double.header.is_double());
+        assert_eq!(double.header.precision_bytes(), 8);
+        assert_eq!(double.header.precision.to_string(), "double");
+
+        assert_eq!(Precision::Single.n_bytes(), 4);
+        assert_eq!(Precision::Double.n_bytes(), 8);
+    }
+
+    #[test]
+    fn clear_coordinates() {
+        let mut tpr = TprParser::new()
+            .keep_coordinate_blocks(true)
+            .parse("tests/test_files/small_aa_2021.tpr")
+            .unwrap();
+        let n_bonds = tpr.topology.bonds.len();
+        assert!(tpr.coordinates.is_some());
+
+        tpr.clear_coordinates();
+        assert!(tpr.coordinates.is_none());
+        assert_eq!(tpr.topology.atoms.len(), 182);
+        assert_eq!(tpr.topology.bonds.len(), n_bonds);
+        assert!(tpr.topology.atoms.iter().all(|atom| atom.position.is_none()
+            && atom.velocity.is_none()
+            && atom.force.is_none()));
+
+        let mut topology = TprFile::parse("tests/test_files/water_2021.tpr")
+            .unwrap()
+            .topology;
+        topology.clear_coordinates();
+        assert!(topology.positions().is_none());
+        assert!(topology.velocities().is_none());
+        assert_eq!(topology.atoms[0].atom_name, "OH2");
+    }
+
+    #[test]
+    fn atom_approx_eq() {
+        let tpr = TprFile::parse("tests/test_files/small_aa_2021.tpr").unwrap();
+        let atom = &tpr.topology.atoms[10];
+        assert!(atom.approx_eq(atom, 0.0));
+        assert!(!atom.approx_eq(&tpr.topology.atoms[11], 0.001));
+
+        let mut shifted = atom.clone();
+        shifted.position.as_mut().unwrap()[0] += 0.0005;
+        assert!(!atom.approx_eq(&shifted, 0.0));
+        assert!(atom.approx_eq(&shifted, 0.001));
+        assert!(!atom.approx_eq(&shifted, 0.0001));
+
+        let mut recharged = atom.clone();
+        recharged.charge_b = Some(atom.charge);
+        assert!(!atom.approx_eq(&recharged, 0.001));
+
+        let mut renamed = atom.clone();
+        renamed.atom_name.push('X');
+        assert!(!atom.approx_eq(&renamed, 1.0));
+
+        let mut no_velocity = atom.clone();
+        no_velocity.velocity = None;
+        assert!(!atom.approx_eq(&no_velocity, 1.0));
+    }
+
+    #[test]
+    fn topology_approx_eq() {
+        let tpr = TprFile::parse("tests/test_files/small_aa_2021.tpr").unwrap();
+        let bytes = std::fs::read("tests/test_files/small_aa_2021.tpr").unwrap();
+        let same = TprFile::parse_bytes(&bytes).unwrap();
+        assert!(tpr.topology.approx_eq(&same.topology, 0.0));
+
+        let mut translated = tpr.topology.clone();
+        translated.translate([0.0001, 0.0, 0.0]);
+        assert!(tpr.topology.approx_eq(&translated, 0.001));
+        assert!(!tpr.topology.approx_eq(&translated, 0.00001));
+
+        let mut sorted = tpr.topology.clone();
+        sorted.bonds.reverse();
+        assert!(!tpr.topology.approx_eq(&sorted, 0.001));
+
+        let other = TprFile::parse("tests/test_files/small_cg_2021.tpr").unwrap();
+        assert!(!tpr.topology.approx_eq(&other.topology, 1.0));
+    }
+
+    #[test]
+    fn atom_new() {
+        let tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+        let parsed = &tpr.topology.atoms[0];
+
+        let atom = Atom::new("OH2", 1, "TIP3", 1, parsed.mass, parsed.charge)
+            .with_element(Element::O)
+            .with_position(parsed.position.unwrap())
+            .with_velocity(parsed.velocity.unwrap());
+
+        assert!(atom.approx_eq(parsed, 0.0));
+        assert_eq!(atom.original_residue_number, 1);
+        assert_eq!(atom.chain_id, ' ');
+        assert!(atom.force.is_none());
+        assert_eq!(
+            atom.with_force([1.0, 2.0, 3.0]).force,
+            Some([1.0, 2.0, 3.0])
+        );
+
+        let default = Atom::default();
+        assert!(default.atom_name.is_empty());
+        assert_eq!(default.atom_number, 0);
+        assert_eq!(default.mass, 0.0);
+        assert!(default.element.is_none());
+        assert!(default.position.is_none());
+        assert_eq!(default.chain_id, ' ');
+    }
+
+    #[test]
+    fn validate() {
+        for file in [
+            "tests/test_files/small_aa_2021.tpr",
+            "tests/test_files/small_cg_2016_intermolecular.tpr",
+            "tests/test_files/small_aa_2021_wrapped_resnums.tpr",
+            "tests/test_files/large_5_posres.tpr",
+            "tests/test_files/double_2023.tpr",
+        ] {
+            let tpr = TprParser::new().strict(true).parse(file).unwrap();
+            assert_eq!(tpr.topology.validate(), Ok(()));
+        }
+
+        let tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+
+        let mut topology = tpr.topology.clone();
+        topology.bonds.push(bond!(8, 9));
+        assert_eq!(topology.validate(), Err(ParseTprError::InvalidBond(8, 9)));
+
+        let mut topology = tpr.topology.clone();
+        topology.atoms[4].atom_number = 4;
+        assert_eq!(
+            topology.validate(),
+            Err(ParseTprError::NonSequentialAtomNumber(4, 4))
+        );
+
+        let mut topology = tpr.topology.clone();
+        topology.atoms[4].residue_number = 1;
+        assert_eq!(
+            topology.validate(),
+            Err(ParseTprError::NonMonotonicResidueNumber(4, 1))
+        );
+    }
+
+    #[test]
+    fn system_name() {
+        let tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+        assert_eq!(tpr.system_name, "Three water molecules");
+        assert_eq!(tpr.system_name(), "Three water molecules");
+
+        // `Three water molecules` with `cules` replaced by control characters and whitespace
+        let mut tpr = TprFile::parse("tests/test_files/water_2021_control_name.tpr").unwrap();
+        assert_eq!(tpr.system_name, "Three water mole");
+        assert_eq!(tpr.system_name(), "Three water mole");
+
+        tpr.system_name = String::new();
+        assert_eq!(tpr.system_name(), "Unnamed system");
+        assert!(tpr.summary().starts_with("System name: Unnamed system\n"));
+
+        tpr.system_name = String::from("  ");
+        assert_eq!(tpr.system_name(), "Unnamed system");
+    }
+
+    #[test]
+    fn composition() {
+        let tpr = TprFile::parse("tests/test_files/small_cg_2021.tpr").unwrap();
+        let expected = vec![
+            (String::from("Translocating"), 1),
+            (String::from("POPC"), 2),
+            (String::from("W"), 10),
+            (String::from("CL-"), 1),
+        ];
+        assert_eq!(tpr.composition(), expected);
+
+        let tpr = TprFile::parse("tests/test_files/large_2021_aa.tpr").unwrap();
+        let expected = vec![
+            (String::from("Protein"), 1),
+            (String::from("POPC"), 128),
+            (String::from("SOL"), 5091),
+            (String::from("NA"), 14),
+            (String::from("CL"), 15),
+        ];
+        assert_eq!(tpr.composition(), expected);
+    }
+
+    #[test]
+    fn molecule_ids() {
+        let tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+
+        let expected = [0, 0, 0, 1, 1, 1, 2, 2, 2];
+        for (atom, expected) in tpr.topology.atoms.iter().zip(expected) {
+            assert_eq!(atom.molecule_id, expected);
+        }
+
+        let tpr = TprFile::parse("tests/test_files/small_cg_2021.tpr").unwrap();
+        let atoms = &tpr.topology.atoms;
+
+        // peptide
+        assert!(atoms[..42].iter().all(|atom| atom.molecule_id == 0));
+        // lipids
+        assert!(atoms[42..54].iter().all(|atom| atom.molecule_id == 1));
+        assert!(atoms[54..66].iter().all(|atom| atom.molecule_id == 2));
+        // water beads
+        for (i, atom) in atoms[66..76].iter().enumerate() {
+            assert_eq!(atom.molecule_id, i + 3);
+        }
+        // ion
+        assert_eq!(atoms[76].molecule_id, 13);
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "serde")]
+mod tests_serde {
+    use super::test_utilities::*;
+    use minitpr::{Atom, Element, TprFile, TprHeader};
+    use std::fs::read_to_string;
+
+    #[test]
+    fn to_yaml() {
+        let tpr = TprFile::parse("tests/test_files/small_aa_2021.tpr").unwrap();
+
+        let string = serde_yaml::to_string(&tpr).unwrap();
+        let expected = read_to_string("tests/test_files/small_aa_2021.yaml").unwrap();
+
+        assert_eq!(string, expected);
+    }
+
+    #[test]
+    fn from_yaml() {
+        let expected = TprFile::parse("tests/test_files/small_aa_2021.tpr").unwrap();
+        let from_yaml: TprFile =
+            serde_yaml::from_str(&read_to_string("tests/test_files/small_aa_2021.yaml").unwrap())
+                .unwrap();
+
+        for (a, e) in from_yaml
+            .topology
+            .atoms
+            .iter()
+            .zip(expected.topology.atoms.iter())
+        {
+            test_eq_atom(a, e);
+        }
+
+        assert_eq!(from_yaml.topology.bonds, expected.topology.bonds);
+        assert_eq!(
+            from_yaml.topology.urey_bradley_pairs,
+            expected.topology.urey_bradley_pairs
+        );
+    }
+
+    #[test]
+    fn header_yaml() {
+        let tpr = TprFile::parse("tests/test_files/small_aa_2021.tpr").unwrap();
+
+        let string = serde_yaml::to_string(&tpr.header).unwrap();
+        assert!(string.starts_with("gromacs_version: VERSION 2021.4\n"));
+        assert!(!string.contains("atom_name"));
+
+        let header: TprHeader = serde_yaml::from_str(&string).unwrap();
+        assert_eq!(header.n_atoms, tpr.header.n_atoms);
+        assert_eq!(header.tpr_version, tpr.header.tpr_version);
+        assert_eq!(header.body_size, tpr.header.body_size);
+    }
+
+    #[test]
+    fn atom_without_coordinates_yaml() {
+        let atom = atom!("BB", 1, "LEU", 1, 72.0, 1.0, None, None, None, None);
+
+        let string = serde_yaml::to_string(&atom).unwrap();
+        assert!(!string.contains("position"));
+        assert!(!string.contains("velocity"));
+        assert!(!string.contains("force"));
+
+        let from_yaml: Atom = serde_yaml::from_str(&string).unwrap();
+        test_eq_atom(&from_yaml, &atom);
+    }
+
+    #[test]
+    fn element_yaml() {
+        let atom = atom!(
+            "CL",
+            1,
+            "CL",
+            1,
+            35.453,
+            -1.0,
+            Some(Element::Cl),
+            None,
+            None,
+            None
+        );
+
+        let string = serde_yaml::to_string(&atom).unwrap();
+        assert!(string.contains("element: Cl\n"));
+
+        let from_yaml: Atom = serde_yaml::from_str(&string).unwrap();
+        assert_eq!(from_yaml.element, Some(Element::Cl));
+
+        let invalid = string.replace("element: Cl", "element: Xx");
+        assert!(serde_yaml::from_str::<Atom>(&invalid).is_err());
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "export")]
+mod tests_export {
+    use minitpr::TprFile;
+
+    #[test]
+    fn write_gro() {
+        let tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+
+        let mut buffer = Vec::new();
+        tpr.write_gro(&mut buffer).unwrap();
+
+        let expected = "Three water molecules
+    9
+    1TIP3   OH2    1   9.380  12.529   0.048  0.0000  0.0000  0.0000
+    1TIP3    H1    2   9.325  12.576   8.182  0.0000  0.0000  0.0000
+    1TIP3    H2    3   9.462  12.515   0.001  0.0000  0.0000  0.0000
+    2TIP3   OH2    4   8.965  12.835   0.039  0.0000  0.0000  0.0000
+    2TIP3    H1    5   8.935  12.744   0.039  0.0000  0.0000  0.0000
+    2TIP3    H2    6   8.994  12.850   8.145  0.0000  0.0000  0.0000
+    3TIP3   OH2    7   9.079  11.643   0.035  0.0000  0.0000  0.0000
+    3TIP3    H1    8   8.987  11.662   0.050  0.0000  0.0000  0.0000
+    3TIP3    H2    9   9.092  11.656   8.137  0.0000  0.0000  0.0000
+  13.06710  13.06710   8.19600
+";
+
+        assert_eq!(String::from_utf8(buffer).unwrap(), expected);
+    }
+
+    #[test]
+    fn write_gro_nobox() {
+        let tpr = TprFile::parse("tests/test_files/water_2021_nobox.tpr").unwrap();
+
+        let mut buffer = Vec::new();
+        tpr.write_gro(&mut buffer).unwrap();
+        let string = String::from_utf8(buffer).unwrap();
+
+        assert_eq!(string.lines().count(), 12);
+        assert_eq!(
+            string.lines().last().unwrap(),
+            "   0.00000   0.00000   0.00000"
+        );
+    }
+
+    #[test]
+    fn write_gro_triclinic() {
+        let tpr = TprFile::parse("tests/test_files/triclinic_2021.tpr").unwrap();
+
+        let mut buffer = Vec::new();
+        tpr.write_gro(&mut buffer).unwrap();
+        let string = String::from_utf8(buffer).unwrap();
+        let lines: Vec<&str> = string.lines().collect();
+
+        assert_eq!(lines.len(), 53);
+        assert_eq!(lines[1], "   50");
+        assert_eq!(
+            lines[2],
+            "    1THR     BB    1   2.197   0.567   1.224  0.0000  0.0000  0.0000"
+        );
+        assert_eq!(
+            lines[52],
+            "   5.29700   4.78912   2.22778   0.00000   0.00000   0.84445   0.00000   1.01785  -1.69043"
+        );
+    }
+
+    #[test]
+    fn write_pdb() {
+        let tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+
+        let mut buffer = Vec::new();
+        tpr.write_pdb(&mut buffer).unwrap();
+
+        let expected = "TITLE     Three water molecules
+CRYST1  130.671  130.671   81.960  90.00  90.00  90.00 P 1           1
+HETATM    1  OH2 TIP3    1      93.800 125.290   0.480  1.00  0.00           O
+HETATM    2  H1  TIP3    1      93.250 125.760  81.820  1.00  0.00           H
+HETATM    3  H2  TIP3    1      94.620 125.150   0.010  1.00  0.00           H
+HETATM    4  OH2 TIP3    2      89.650 128.350   0.390  1.00  0.00           O
+HETATM    5  H1  TIP3    2      89.350 127.440   0.390  1.00  0.00           H
+HETATM    6  H2  TIP3    2      89.940 128.500  81.450  1.00  0.00           H
+HETATM    7  OH2 TIP3    3      90.790 116.430   0.350  1.00  0.00           O
+HETATM    8  H1  TIP3    3      89.870 116.620   0.500  1.00  0.00           H
+HETATM    9  H2  TIP3    3      90.920 116.560  81.370  1.00  0.00           H
+CONECT    1    2    3
+CONECT    2    1
+CONECT    3    1
+CONECT    4    5    6
+CONECT    5    4
+CONECT    6    4
+CONECT    7    8    9
+CONECT    8    7
+CONECT    9    7
+END
+";
+
+        assert_eq!(String::from_utf8(buffer).unwrap(), expected);
+    }
+
+    #[test]
+    fn write_pdb_protein() {
+        let tpr = TprFile::parse("tests/test_files/small_aa_2021.tpr").unwrap();
+
+        let mut buffer = Vec::new();
+        tpr.write_pdb(&mut buffer).unwrap();
+        let string = String::from_utf8(buffer).unwrap();
+        let lines: Vec<&str> = string.lines().collect();
+
+        assert_eq!(
+            lines[2],
+            "ATOM      1  N   LEU A   1      23.720  47.100  14.670  1.00  0.00           N"
+        );
+        assert!(lines
+            .iter()
+            .any(|line| line.starts_with("HETATM") && line.contains("POPC")));
+        assert_eq!(
+            lines
+                .iter()
+                .filter(|line| line.starts_with("ATOM") || line.starts_with("HETATM"))
+                .count(),
+            182
+        );
+        assert_eq!(*lines.last().unwrap(), "END");
+    }
+
+    #[test]
+    fn write_pdb_insertion_code() {
+        let mut tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+        for atom in tpr.topology.atoms.iter_mut().skip(3).take(3) {
+            atom.insertion_code = Some('A');
+        }
+
+        let mut buffer = Vec::new();
+        tpr.write_pdb(&mut buffer).unwrap();
+        let string = String::from_utf8(buffer).unwrap();
+        let lines: Vec<&str> = string.lines().collect();
+
+        assert_eq!(
+            lines[4],
+            "HETATM    3  H2  TIP3    1      94.620 125.150   0.010  1.00  0.00           H"
+        );
+        assert_eq!(
+            lines[5],
+            "HETATM    4  OH2 TIP3    2A     89.650 128.350   0.390  1.00  0.00           O"
+        );
+    }
+
+    #[test]
+    fn write_atoms_csv() {
+        let tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+
+        let mut buffer = Vec::new();
+        tpr.write_atoms_csv(&mut buffer).unwrap();
+
+        let expected = "atom_number,atom_name,residue_number,residue_name,mass,charge,element,x,y,z
+1,OH2,1,TIP3,15.999400,-0.834000,O,9.3800,12.5290,0.0480
+2,H1,1,TIP3,1.008000,0.417000,H,9.3250,12.5760,8.1820
+3,H2,1,TIP3,1.008000,0.417000,H,9.4620,12.5150,0.0010
+4,OH2,2,TIP3,15.999400,-0.834000,O,8.9650,12.8350,0.0390
+5,H1,2,TIP3,1.008000,0.417000,H,8.9350,12.7440,0.0390
+6,H2,2,TIP3,1.008000,0.417000,H,8.9940,12.8500,8.1450
+7,OH2,3,TIP3,15.999400,-0.834000,O,9.0790,11.6430,0.0350
+8,H1,3,TIP3,1.008000,0.417000,H,8.9870,11.6620,0.0500
+9,H2,3,TIP3,1.008000,0.417000,H,9.0920,11.6560,8.1370
+";
+
+        assert_eq!(String::from_utf8(buffer).unwrap(), expected);
+    }
+
+    #[test]
+    fn write_atoms_csv_missing_data() {
+        let mut tpr = TprFile::parse("tests/test_files/water_2021_no_coordinates.tpr").unwrap();
+        tpr.topology.atoms[0].atom_name = String::from("O,\"H\"");
+        tpr.topology.atoms[1].element = None;
+
+        let mut buffer = Vec::new();
+        tpr.write_atoms_csv(&mut buffer).unwrap();
+        let string = String::from_utf8(buffer).unwrap();
+        let lines: Vec<&str> = string.lines().collect();
+
+        assert_eq!(lines.len(), 10);
+        assert_eq!(
+            lines[1],
+            "1,\"O,\"\"H\"\"\",1,TIP3,15.999400,-0.834000,O,,,"
+        );
+        assert_eq!(lines[2], "2,H1,1,TIP3,1.008000,0.417000,,,,");
+    }
+
+    #[test]
+    fn write_bonds_csv() {
+        let tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+
+        let mut buffer = Vec::new();
+        tpr.write_bonds_csv(&mut buffer, false).unwrap();
+        let expected = "atom1,atom2
+0,1
+0,2
+3,4
+3,5
+6,7
+6,8
+";
+        assert_eq!(String::from_utf8(buffer).unwrap(), expected);
+
+        let mut buffer = Vec::new();
+        tpr.write_bonds_csv(&mut buffer, true).unwrap();
+        let expected = "atom1,atom2
+1,2
+1,3
+4,5
+4,6
+7,8
+7,9
+";
+        assert_eq!(String::from_utf8(buffer).unwrap(), expected);
+    }
+
+    #[test]
+    fn write_bonds_csv_cg() {
+        let tpr = TprFile::parse("tests/test_files/small_cg_2021.tpr").unwrap();
+
+        let mut buffer = Vec::new();
+        tpr.write_bonds_csv(&mut buffer, false).unwrap();
+        let string = String::from_utf8(buffer).unwrap();
+        let lines: Vec<&str> = string.lines().collect();
+
+        assert_eq!(lines.len(), tpr.topology.bonds.len() + 1);
+        for (line, bond) in lines[1..].iter().zip(tpr.topology.bonds.iter()) {
+            assert_eq!(*line, format!("{},{}", bond.atom1, bond.atom2));
+        }
+    }
+
+    #[test]
+    fn write_dot() {
+        let tpr = TprFile::parse("tests/test_files/water_2021.tpr").unwrap();
+
+        let mut buffer = Vec::new();
+        tpr.topology.write_dot(&mut buffer).unwrap();
+
+        let expected = "graph topology {
+  node [style=filled];
+  0 [label=\"OH2 1\", fillcolor=\"#FF0D0D\"];
+  1 [label=\"H1 2\", fillcolor=\"#FFFFFF\"];
+  2 [label=\"H2 3\", fillcolor=\"#FFFFFF\"];
+  3 [label=\"OH2 4\", fillcolor=\"#FF0D0D\"];
+  4 [label=\"H1 5\", fillcolor=\"#FFFFFF\"];
+  5 [label=\"H2 6\", fillcolor=\"#FFFFFF\"];
+  6 [label=\"OH2 7\", fillcolor=\"#FF0D0D\"];
+  7 [label=\"H1 8\", fillcolor=\"#FFFFFF\"];
+  8 [label=\"H2 9\", fillcolor=\"#FFFFFF\"];
+  0 -- 1;
+  0 -- 2;
+  3 -- 4;
+  3 -- 5;
+  6 -- 7;
+  6 -- 8;
+}
+";
+
+        assert_eq!(String::from_utf8(buffer).unwrap(), expected);
+    }
+
+    #[test]
+    fn write_dot_unknown_elements() {
+        let tpr = TprFile::parse("tests/test_files/small_cg_2021.tpr").unwrap();
+
+        let mut buffer = Vec::new();
+        tpr.topology.write_dot(&mut buffer).unwrap();
+        let string = String::from_utf8(buffer).unwrap();
+
+        assert_eq!(
+            string
+                .lines()
+                .filter(|line| line.contains("label="))
+                .count(),
+            tpr.topology.atoms.len()
+        );
+        assert_eq!(
+            string.lines().filter(|line| line.contains(" -- ")).count(),
+            tpr.topology.bonds.len()
+        );
+        assert!(string
+            .lines()
+            .filter(|line| line.contains("label="))
+            .all(|line| line.contains("#D3D3D3")));
     }
 }